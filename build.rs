@@ -0,0 +1,50 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+// generates the table of assets compiled into the binary by the embedded_assets feature
+fn main() {
+    println!("cargo:rerun-if-changed=assets");
+
+    let assets_path = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets");
+    let mut files = Vec::new();
+    if env::var_os("CARGO_FEATURE_EMBEDDED_ASSETS").is_some() {
+        collect_files(&assets_path, &mut files);
+    }
+    files.sort();
+
+    let entries = files
+        .iter()
+        .map(|path| {
+            let relative_path = path.strip_prefix(&assets_path).unwrap();
+            format!(
+                "    ({:?}, include_bytes!({:?})),\n",
+                relative_path.to_string_lossy().replace('\\', "/"),
+                path
+            )
+        })
+        .collect::<String>();
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("embedded_assets.rs");
+    fs::write(
+        out_path,
+        format!(
+            "pub const EMBEDDED_ASSETS: &[(&str, &[u8])] = &[\n{}];\n",
+            entries
+        ),
+    )
+    .unwrap();
+}
+
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(directory).unwrap().flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
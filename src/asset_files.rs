@@ -0,0 +1,83 @@
+#[cfg(feature = "embedded_assets")]
+use crate::prelude::*;
+
+#[cfg(feature = "embedded_assets")]
+include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+
+// every asset path relative to the asset directory, e.g. themes/amber/theme.ron
+#[cfg(not(feature = "embedded_assets"))]
+pub fn asset_files() -> Vec<String> {
+    use bevy::asset::io::file::FileAssetReader;
+    use std::{fs, path::Path};
+
+    fn collect_files(root: &Path, directory: &Path, files: &mut Vec<String>) {
+        let Ok(entries) = fs::read_dir(directory) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                collect_files(root, &path, files);
+            } else if let Ok(relative_path) = path.strip_prefix(root) {
+                files.push(relative_path.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+
+    let assets_path = FileAssetReader::get_base_path().join("assets");
+    let mut files = Vec::new();
+    collect_files(&assets_path, &assets_path, &mut files);
+    files
+}
+
+#[cfg(feature = "embedded_assets")]
+pub fn asset_files() -> Vec<String> {
+    EMBEDDED_ASSETS
+        .iter()
+        .map(|(path, _)| path.to_string())
+        .collect()
+}
+
+#[cfg(not(feature = "embedded_assets"))]
+pub fn read_asset(path: &str) -> Option<Vec<u8>> {
+    use bevy::asset::io::file::FileAssetReader;
+
+    std::fs::read(FileAssetReader::get_base_path().join("assets").join(path)).ok()
+}
+
+#[cfg(feature = "embedded_assets")]
+pub fn read_asset(path: &str) -> Option<Vec<u8>> {
+    EMBEDDED_ASSETS
+        .iter()
+        .find(|(embedded_path, _)| *embedded_path == path)
+        .map(|(_, bytes)| bytes.to_vec())
+}
+
+// serves the assets compiled into the binary in place of the asset directory,
+// so it has to be added before the AssetPlugin
+#[cfg(feature = "embedded_assets")]
+pub struct EmbeddedAssetsPlugin;
+
+#[cfg(feature = "embedded_assets")]
+impl Plugin for EmbeddedAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        use bevy::asset::io::{
+            memory::{Dir, MemoryAssetReader},
+            AssetSource, AssetSourceId,
+        };
+        use std::path::Path;
+
+        let root = Dir::default();
+        for (path, bytes) in EMBEDDED_ASSETS {
+            root.insert_asset(Path::new(path), *bytes);
+        }
+
+        app.register_asset_source(
+            AssetSourceId::Default,
+            AssetSource::build()
+                .with_reader(move || Box::new(MemoryAssetReader { root: root.clone() })),
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::{collections::HashMap, fmt};
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use serde::Deserialize;
+
+use crate::prelude::*;
+
+#[derive(Deserialize, Debug)]
+struct SpriteAtlasManifest {
+    size: (u32, u32),
+    regions: HashMap<String, SpriteRegion>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SpriteRegion {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+// the named regions of the sprite atlas, loaded from a .atlas.ron file
+#[derive(Asset, TypePath, Debug)]
+pub struct SpriteRegions {
+    pub layout: Handle<TextureAtlasLayout>,
+    indices: HashMap<String, usize>,
+}
+
+// a single texture holding every sprite, with the regions addressed by their name
+#[derive(Resource, Clone, Debug)]
+pub struct SpriteAtlas {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    indices: HashMap<String, usize>,
+}
+
+impl SpriteAtlas {
+    pub fn new(texture: Handle<Image>, regions: &SpriteRegions) -> Self {
+        Self {
+            texture,
+            layout: regions.layout.clone(),
+            indices: regions.indices.clone(),
+        }
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn texture_atlas(&self, name: &str) -> TextureAtlas {
+        let index = self.index(name).unwrap_or_else(|| {
+            warn!("Missing sprite {}", name);
+            0
+        });
+
+        TextureAtlas {
+            layout: self.layout.clone(),
+            index,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SpriteAtlasLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for SpriteAtlasLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpriteAtlasLoaderError::Io(error) => write!(f, "could not read atlas: {}", error),
+            SpriteAtlasLoaderError::Ron(error) => write!(f, "could not parse atlas: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SpriteAtlasLoaderError {}
+
+#[derive(Default)]
+pub struct SpriteAtlasLoader;
+
+impl AssetLoader for SpriteAtlasLoader {
+    type Asset = SpriteRegions;
+    type Settings = ();
+    type Error = SpriteAtlasLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<SpriteRegions, SpriteAtlasLoaderError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(SpriteAtlasLoaderError::Io)?;
+        let manifest: SpriteAtlasManifest =
+            ron::de::from_bytes(&bytes).map_err(SpriteAtlasLoaderError::Ron)?;
+
+        let (width, height) = manifest.size;
+        let mut layout = TextureAtlasLayout::new_empty(UVec2::new(width, height));
+        let indices = manifest
+            .regions
+            .into_iter()
+            .map(|(name, region)| {
+                let index = layout.add_texture(URect::new(
+                    region.x,
+                    region.y,
+                    region.x + region.width,
+                    region.y + region.height,
+                ));
+                (name, index)
+            })
+            .collect();
+
+        Ok(SpriteRegions {
+            layout: load_context.add_labeled_asset("layout".into(), layout),
+            indices,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["atlas.ron"]
+    }
+}
@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+
+use bevy::{
+    app::{PluginGroupBuilder, ScheduleRunnerPlugin},
+    audio::AudioPlugin,
+    render::{settings::WgpuSettings, RenderPlugin},
+    window::{ExitCondition, WindowMode},
+    winit::WinitPlugin,
+};
+use clap::Parser;
+
+use invader::prelude::*;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Space Invaders, written with Bevy")]
+pub struct Cli {
+    /// Seed for the random number generator, picked at random if not given
+    #[arg(long, conflicts_with = "replay")]
+    pub seed: Option<u64>,
+
+    /// Round to start on, which sets the height the invaders start from
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..), conflicts_with = "replay")]
+    pub level: u8,
+
+    /// Number of lifes to start with, as many as the difficulty gives if not given
+    #[arg(long, value_parser = clap::value_parser!(i8).range(1..=9), conflicts_with = "replay")]
+    pub lifes: Option<i8>,
+
+    /// Difficulty to play on: easy, normal, hard or arcade
+    #[arg(long, default_value_t = DifficultyPreset::Normal, conflicts_with = "replay")]
+    pub difficulty: DifficultyPreset,
+
+    /// Nudge the difficulty to how well the game goes
+    #[arg(long, conflicts_with = "replay")]
+    pub adaptive: bool,
+
+    /// Factor to scale the native resolution of the window by
+    #[arg(long, default_value_t = WINDOW_SCALE as u8, value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub scale: u8,
+
+    /// Start in borderless fullscreen
+    #[arg(long, conflicts_with = "headless")]
+    pub fullscreen: bool,
+
+    /// Turn off all sounds
+    #[arg(long)]
+    pub mute: bool,
+
+    /// Run without a window as fast as possible, for playing back a replay
+    #[arg(long, requires = "replay")]
+    pub headless: bool,
+
+    /// Play back a replay recorded with --record
+    #[arg(long, value_name = "FILE", conflicts_with = "record")]
+    pub replay: Option<PathBuf>,
+
+    /// Record the game to a replay file
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Start playing right away instead of showing the menu
+    #[arg(long)]
+    pub skip_menu: bool,
+}
+
+impl Cli {
+    // parses the arguments and loads the replay, exiting with a usage error if either fails
+    pub fn parse_and_validate() -> (Self, Option<Replay>) {
+        let cli = Cli::parse();
+
+        let replay = cli.replay.as_ref().map(|path| {
+            Replay::load(path).unwrap_or_else(|error| {
+                clap::Error::raw(
+                    clap::error::ErrorKind::ValueValidation,
+                    format!("could not load replay {}: {}\n", path.display(), error),
+                )
+                .exit()
+            })
+        });
+
+        (cli, replay)
+    }
+
+    pub fn plugins(&self) -> PluginGroupBuilder {
+        let window = Window {
+            title: "Space Invader".into(),
+            resolution: (
+                SCREEN_WIDTH * self.scale as f32,
+                SCREEN_HEIGHT * self.scale as f32,
+            )
+                .into(),
+            mode: match self.fullscreen {
+                true => WindowMode::BorderlessFullscreen,
+                false => WindowMode::Windowed,
+            },
+            ..default()
+        };
+
+        let plugins = DefaultPlugins
+            .set(AudioPlugin {
+                global_volume: GlobalVolume::new(if self.mute { 0. } else { 1. }),
+                ..default()
+            })
+            // keep the pixel art and the bitmap font crisp when scaled
+            .set(ImagePlugin::default_nearest());
+
+        if !self.headless {
+            return plugins.set(WindowPlugin {
+                primary_window: Some(window),
+                ..default()
+            });
+        }
+
+        // without a window nothing is rendered, but the assets are still loaded
+        plugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: WgpuSettings {
+                    backends: None,
+                    ..default()
+                }
+                .into(),
+                ..default()
+            })
+            .disable::<WinitPlugin>()
+            .add(ScheduleRunnerPlugin::run_loop(Duration::ZERO))
+    }
+
+    // the settings of the game, taken from the replay when one is played back
+    pub fn apply(self, app: &mut App, replay: Option<Replay>) {
+        // played back games don't make it into the high scores on disk
+        if let Some(replay) = replay {
+            ReplayPlayer::install(app, replay, self.headless);
+            return;
+        }
+
+        let settings = GameSettings {
+            seed: self.seed.unwrap_or_else(random),
+            level: self.level,
+            lifes: self.lifes.unwrap_or(self.difficulty.lifes()),
+            difficulty: self.difficulty,
+            adaptive: self.adaptive,
+        };
+
+        HighScores::install(app, PathBuf::from(HIGH_SCORES_FILE));
+
+        match self.record {
+            Some(path) => ReplayRecorder::install(app, path, settings),
+            None => {
+                if self.skip_menu {
+                    AfterLoading::skip_menu(app);
+                }
+                app.insert_resource(settings);
+            }
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use bevy::{
+    math::bounding::{Aabb2d, BoundingVolume, IntersectsVolume},
+    render::render_resource::TextureFormat,
+};
+
+use crate::prelude::*;
+
+// what a collider is, which decides what it can collide with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionLayer {
+    PlayerShot,
+    EnemyShot,
+    Invader,
+    Bunker,
+    Ufo,
+    Cannon,
+}
+
+impl CollisionLayer {
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+// the layers a collider reports collisions with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CollisionMask(u8);
+
+impl CollisionMask {
+    pub const NONE: Self = Self(0);
+
+    pub fn with(self, layer: CollisionLayer) -> Self {
+        Self(self.0 | layer.bit())
+    }
+
+    pub fn contains(self, layer: CollisionLayer) -> bool {
+        self.0 & layer.bit() != 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColliderShape {
+    // the whole box
+    #[default]
+    Box,
+    // only the visible pixels of the current sprite frame, stretched over the box,
+    // which should be the size the sprite is drawn at
+    Pixels,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Collider {
+    pub size: Vec2,
+    pub layer: CollisionLayer,
+    pub mask: CollisionMask,
+    pub shape: ColliderShape,
+}
+
+impl Collider {
+    // collides with nothing by itself, but can still be collided with
+    pub fn new(width: f32, height: f32, layer: CollisionLayer) -> Self {
+        Self {
+            size: Vec2::new(width, height),
+            layer,
+            mask: CollisionMask::NONE,
+            shape: ColliderShape::Box,
+        }
+    }
+
+    pub fn pixel_perfect(mut self) -> Self {
+        self.shape = ColliderShape::Pixels;
+        self
+    }
+
+    pub fn colliding_with(mut self, layers: &[CollisionLayer]) -> Self {
+        for layer in layers {
+            self.mask = self.mask.with(*layer);
+        }
+        self
+    }
+
+    fn bounding_box(&self, position: Vec2) -> Aabb2d {
+        Aabb2d::new(position, self.size / 2.)
+    }
+}
+
+// which pixels of a sprite frame can be hit
+#[derive(Debug)]
+pub struct PixelMask {
+    width: u32,
+    height: u32,
+    solid: Vec<bool>,
+}
+
+impl PixelMask {
+    fn from_image(image: &Image, region: URect) -> Option<Self> {
+        if !matches!(
+            image.texture_descriptor.format,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+        ) {
+            return None;
+        }
+
+        let solid = (region.min.y..region.max.y)
+            .flat_map(|y| (region.min.x..region.max.x).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let alpha = (y * image.width() + x) as usize * 4 + 3;
+                image.data.get(alpha).is_some_and(|alpha| *alpha > 0)
+            })
+            .collect();
+
+        Some(Self {
+            width: region.width(),
+            height: region.height(),
+            solid,
+        })
+    }
+
+    // u and v run from 0 to 1, starting at the top left
+    fn is_solid(&self, u: f32, v: f32) -> bool {
+        if !(0. ..1.).contains(&u) || !(0. ..1.).contains(&v) {
+            return false;
+        }
+
+        let x = (u * self.width as f32) as u32;
+        let y = (v * self.height as f32) as u32;
+        self.solid[(y * self.width + x) as usize]
+    }
+}
+
+// the pixel masks of every region of the sprite atlas, by their index
+#[derive(Resource, Default, Debug)]
+pub struct PixelMasks(HashMap<usize, PixelMask>);
+
+pub fn build_pixel_masks(
+    mut commands: Commands,
+    atlas: Option<Res<SpriteAtlas>>,
+    images: Res<Assets<Image>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+) {
+    let mut masks = PixelMasks::default();
+
+    if let Some(atlas) = atlas {
+        if let (Some(image), Some(layout)) =
+            (images.get(&atlas.texture), layouts.get(&atlas.layout))
+        {
+            for (index, region) in layout.textures.iter().enumerate() {
+                if let Some(mask) = PixelMask::from_image(image, *region) {
+                    masks.0.insert(index, mask);
+                }
+            }
+        }
+    }
+
+    // colliders without a mask fall back to their box
+    info!("Built {} pixel masks", masks.0.len());
+    commands.insert_resource(masks);
+}
+
+// a collider that moves fast enough to pass through thin ones between two frames,
+// which is checked against everything it passed on the way instead
+#[derive(Component, Debug)]
+pub struct Swept {
+    from: Vec2,
+}
+
+impl Swept {
+    pub fn new(from: Vec2) -> Self {
+        Self { from }
+    }
+}
+
+// a collider ran into another one on a layer of its mask, which is reported from the
+// side of the one with the mask, so twice when both have each other in theirs
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Collision {
+    pub entity: Entity,
+    pub layer: CollisionLayer,
+    pub other: Entity,
+    pub other_layer: CollisionLayer,
+    // where the other collider was when it was hit
+    pub position: Vec2,
+    // how far the other collider is from where the colliding one started this frame
+    pub distance: f32,
+}
+
+// the distance between the points two pixel masks are compared at, which is the
+// size of a pixel of the sprites as they are drawn
+const NARROW_PHASE_STEP: f32 = 0.5;
+
+// a collider as it is placed this frame
+struct Placed<'a> {
+    // the box it swept through this frame
+    bounding_box: Aabb2d,
+    // the box its pixel mask is stretched over
+    sprite_box: Aabb2d,
+    pixel_mask: Option<&'a PixelMask>,
+}
+
+impl Placed<'_> {
+    fn covers(&self, point: Vec2) -> bool {
+        match self.pixel_mask {
+            Some(pixel_mask) => {
+                let size = self.sprite_box.max - self.sprite_box.min;
+                pixel_mask.is_solid(
+                    (point.x - self.sprite_box.min.x) / size.x,
+                    (self.sprite_box.max.y - point.y) / size.y,
+                )
+            }
+            None => true,
+        }
+    }
+
+    // whether any point in the overlap of the boxes is covered by both colliders
+    fn overlaps(&self, other: &Placed) -> bool {
+        if !self.bounding_box.intersects(&other.bounding_box) {
+            return false;
+        }
+        if self.pixel_mask.is_none() && other.pixel_mask.is_none() {
+            return true;
+        }
+
+        let min = self.bounding_box.min.max(other.bounding_box.min);
+        let max = self.bounding_box.max.min(other.bounding_box.max);
+        let steps = ((max - min) / NARROW_PHASE_STEP).ceil().as_uvec2();
+
+        (0..steps.y.max(1)).any(|y| {
+            (0..steps.x.max(1)).any(|x| {
+                let point =
+                    (min + (Vec2::new(x as f32, y as f32) + 0.5) * NARROW_PHASE_STEP).min(max);
+                self.covers(point) && other.covers(point)
+            })
+        })
+    }
+}
+
+type ColliderData = (
+    Entity,
+    &'static Transform,
+    &'static Collider,
+    Option<&'static Swept>,
+    Option<&'static TextureAtlas>,
+);
+
+pub fn detect_collisions(
+    collider_query: Query<ColliderData>,
+    pixel_masks: Res<PixelMasks>,
+    mut collision_event_writer: EventWriter<Collision>,
+) {
+    let colliders = collider_query
+        .iter()
+        .map(
+            |(entity, transform, collider, maybe_swept, maybe_texture_atlas)| {
+                let position = transform.translation.truncate();
+                let from = maybe_swept.map_or(position, |swept| swept.from);
+                let sprite_box = collider.bounding_box(position);
+                let pixel_mask = match collider.shape {
+                    ColliderShape::Pixels => maybe_texture_atlas
+                        .and_then(|texture_atlas| pixel_masks.0.get(&texture_atlas.index)),
+                    ColliderShape::Box => None,
+                };
+
+                let placed = Placed {
+                    bounding_box: sprite_box.merge(&collider.bounding_box(from)),
+                    sprite_box,
+                    pixel_mask,
+                };
+                (entity, collider, position, from, placed)
+            },
+        )
+        .collect::<Vec<_>>();
+
+    // only colliders with a mask go looking for collisions
+    let colliding = colliders
+        .iter()
+        .filter(|(_, collider, ..)| collider.mask != CollisionMask::NONE);
+
+    for (entity, collider, _, from, placed) in colliding {
+        for (other, other_collider, other_position, _, other_placed) in colliders.iter() {
+            if other == entity || !collider.mask.contains(other_collider.layer) {
+                continue;
+            }
+
+            if placed.overlaps(other_placed) {
+                collision_event_writer.send(Collision {
+                    entity: *entity,
+                    layer: collider.layer,
+                    other: *other,
+                    other_layer: other_collider.layer,
+                    position: *other_position,
+                    distance: from.distance(*other_position),
+                });
+            }
+        }
+    }
+}
+
+// the next frame sweeps from where the colliders are now
+pub fn update_sweeps(mut swept_query: Query<(&mut Swept, &Transform)>) {
+    for (mut swept, transform) in swept_query.iter_mut() {
+        swept.from = transform.translation.truncate();
+    }
+}
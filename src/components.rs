@@ -1,4 +1,5 @@
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
 use crate::prelude::*;
 
@@ -6,11 +7,53 @@ use crate::prelude::*;
 pub struct Player {
     pub lifes: i8,
     pub score: i32,
+    pub shots_fired: u32,
+    // everything the laser hit, including bombs
+    pub hits: u32,
+    pub invaders_destroyed: u32,
+    pub ufos_hit: u32,
+    // the shots and hits of the level being played
+    pub level_shots_fired: u32,
+    pub level_hits: u32,
 }
 
 impl Player {
-    pub fn new() -> Self {
-        Self { lifes: 3, score: 0 }
+    pub fn new(lifes: i8) -> Self {
+        Self {
+            lifes,
+            score: 0,
+            shots_fired: 0,
+            hits: 0,
+            invaders_destroyed: 0,
+            ufos_hit: 0,
+            level_shots_fired: 0,
+            level_hits: 0,
+        }
+    }
+
+    pub fn start_level(&mut self) {
+        self.level_shots_fired = 0;
+        self.level_hits = 0;
+    }
+
+    // what clearing a level earns on top of the invaders shot
+    pub fn level_bonuses(&self) -> Vec<(String, i32)> {
+        vec![
+            ("Lifes bonus".into(), self.lifes as i32 * 50),
+            (
+                "Accuracy bonus".into(),
+                (self.level_accuracy() * 100.).round() as i32 * 5,
+            ),
+        ]
+    }
+
+    // the share of the shots that hit something, from 0 to 1
+    pub fn accuracy(&self) -> f32 {
+        share_of_hits(self.hits, self.shots_fired)
+    }
+
+    pub fn level_accuracy(&self) -> f32 {
+        share_of_hits(self.level_hits, self.level_shots_fired)
     }
 
     pub fn kill(&mut self) {
@@ -31,19 +74,29 @@ impl Player {
     }
 }
 
+fn share_of_hits(hits: u32, shots_fired: u32) -> f32 {
+    match shots_fired {
+        0 => 0.,
+        shots_fired => hits as f32 / shots_fired as f32,
+    }
+}
+
 #[derive(Resource)]
 pub struct EnemyMovement {
     pub direction: f32,
     pub speed: f32,
     pub advance: bool,
+    // the invaders take a step whenever it finishes
+    pub timer: Timer,
 }
 
 impl EnemyMovement {
-    pub fn new() -> Self {
+    pub fn new(difficulty: &Difficulty) -> Self {
         Self {
             direction: 1.,
             speed: SPRITE_SIZE / 4.,
             advance: false,
+            timer: Timer::new(difficulty.march_interval, TimerMode::Repeating),
         }
     }
 
@@ -52,15 +105,6 @@ impl EnemyMovement {
     }
 }
 
-#[derive(Event, Default)]
-pub struct EnemyAdvancement;
-
-#[derive(Event, Default)]
-pub struct HitEvent;
-
-#[derive(Event, Default)]
-pub struct PlayerHitEvent;
-
 #[derive(Component, Clone, Copy, Debug)]
 pub struct Cannon;
 
@@ -69,17 +113,18 @@ pub struct CannonBundle {
     marker: Cannon,
     sprite: SpriteBundle,
     texture_atlas: TextureAtlas,
-    size: Size,
+    collider: Collider,
 }
 
 impl CannonBundle {
-    pub fn new(texture: Handle<Image>, layout: Handle<TextureAtlasLayout>) -> Self {
+    pub fn new(atlas: &SpriteAtlas) -> Self {
         Self {
             marker: Cannon,
             sprite: SpriteBundle {
-                texture,
+                texture: atlas.texture.clone(),
                 sprite: Sprite {
                     color: SPRITE_COLOR,
+                    custom_size: Some(Vec2::splat(SPRITE_SIZE)),
                     ..default()
                 },
                 transform: Transform {
@@ -88,11 +133,9 @@ impl CannonBundle {
                 },
                 ..default()
             },
-            texture_atlas: TextureAtlas { layout, index: 0 },
-            size: Size {
-                width: 26.,
-                height: 16.,
-            },
+            texture_atlas: atlas.texture_atlas("cannon"),
+            collider: Collider::new(SPRITE_SIZE, SPRITE_SIZE, CollisionLayer::Cannon)
+                .pixel_perfect(),
         }
     }
 }
@@ -100,120 +143,157 @@ impl CannonBundle {
 #[derive(Component, Clone, Copy, Debug)]
 pub struct LaserBeam;
 
+// a shot that carries on through everything it hits
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Piercing;
+
 #[derive(Bundle)]
 pub struct LaserBeamBundle {
     marker: LaserBeam,
     sprite: SpriteBundle,
-    size: Size,
+    texture_atlas: TextureAtlas,
+    collider: Collider,
+    swept: Swept,
 }
 
+// the laser and the bombs are drawn at half the size of the other sprites
+const SHOT_SPRITE_SIZE: Vec2 = Vec2::new(SPRITE_SIZE / 4., SPRITE_SIZE / 2.);
+
 impl LaserBeamBundle {
-    pub fn new(x: f32, y: f32) -> Self {
+    pub fn new(x: f32, y: f32, atlas: &SpriteAtlas) -> Self {
         Self {
             marker: LaserBeam,
             sprite: SpriteBundle {
+                texture: atlas.texture.clone(),
                 sprite: Sprite {
                     color: SPRITE_COLOR,
+                    custom_size: Some(SHOT_SPRITE_SIZE),
                     ..default()
                 },
                 transform: Transform {
-                    scale: Vec2::new(3., 10.).extend(1.),
                     translation: Vec2::new(x, y + SPRITE_SIZE / 2.).extend(0.),
                     ..default()
                 },
                 ..default()
             },
-            size: Size {
-                width: 1.,
-                height: 10.,
-            },
+            texture_atlas: atlas.texture_atlas("laser"),
+            // the visible pixels of the sprite
+            collider: Collider::new(1., 4., CollisionLayer::PlayerShot).colliding_with(&[
+                CollisionLayer::Invader,
+                CollisionLayer::Ufo,
+                CollisionLayer::EnemyShot,
+                CollisionLayer::Bunker,
+            ]),
+            swept: Swept::new(Vec2::new(x, y + SPRITE_SIZE / 2.)),
         }
     }
 }
 
 #[derive(Component)]
-pub struct Hitable;
+pub struct Hitable {
+    pub kind: HitKind,
+}
 
 #[derive(Component)]
-pub struct Bomb;
+pub struct Bomb {
+    pub dropped_by: EnemyKind,
+}
 
 #[derive(Bundle)]
 pub struct BombBundle {
     marker: Bomb,
     sprite: SpriteBundle,
+    texture_atlas: TextureAtlas,
     hitable: Hitable,
-    size: Size,
-}
-
-#[derive(Component)]
-pub struct Size {
-    pub width: f32,
-    pub height: f32,
+    collider: Collider,
+    swept: Swept,
 }
 
 impl BombBundle {
-    pub fn new(x: f32, y: f32) -> Self {
+    pub fn new(x: f32, y: f32, dropped_by: EnemyKind, atlas: &SpriteAtlas) -> Self {
         Self {
-            marker: Bomb,
+            marker: Bomb { dropped_by },
             sprite: SpriteBundle {
+                texture: atlas.texture.clone(),
                 sprite: Sprite {
                     color: SPRITE_COLOR,
+                    custom_size: Some(SHOT_SPRITE_SIZE),
                     ..default()
                 },
                 transform: Transform {
-                    scale: Vec2::new(10., 30.).extend(1.),
                     translation: Vec2::new(x, y).extend(0.),
                     ..default()
                 },
                 ..default()
             },
-            hitable: Hitable,
-            size: Size {
-                width: 10.,
-                height: 30.,
+            texture_atlas: atlas.texture_atlas(dropped_by.bomb_sprite_name()),
+            hitable: Hitable {
+                kind: HitKind::Bomb,
             },
+            // the visible pixels of the widest and tallest of the bomb sprites
+            collider: Collider::new(3., 8., CollisionLayer::EnemyShot)
+                .colliding_with(&[CollisionLayer::Cannon, CollisionLayer::Bunker]),
+            swept: Swept::new(Vec2::new(x, y)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnemyKind {
+    Squid,
+    Crab,
+    Octopus,
+}
+
+impl EnemyKind {
+    pub fn sprite_name(&self) -> &'static str {
+        match self {
+            EnemyKind::Squid => "squid_0",
+            EnemyKind::Crab => "crab_0",
+            EnemyKind::Octopus => "octopus_0",
+        }
+    }
+
+    // each kind drops one of the three kinds of bombs of the arcade
+    pub fn bomb_sprite_name(&self) -> &'static str {
+        match self {
+            EnemyKind::Squid => "bomb_squiggly_0",
+            EnemyKind::Crab => "bomb_plunger_0",
+            EnemyKind::Octopus => "bomb_rolling_0",
         }
     }
 }
 
 #[derive(Component, Clone)]
 pub struct Enemy {
-    pub sprite_file_name: String,
+    pub kind: EnemyKind,
     points: i32,
-    width: f32,
-    height: f32,
 }
 
 impl Enemy {
     pub fn squid() -> Self {
         Enemy {
-            sprite_file_name: "squid.png".into(),
+            kind: EnemyKind::Squid,
             points: 30,
-            width: 16.,
-            height: 16.,
         }
     }
 
     pub fn crab() -> Self {
         Enemy {
-            sprite_file_name: "crab.png".into(),
+            kind: EnemyKind::Crab,
             points: 20,
-            width: 11.,
-            height: 16.,
         }
     }
 
     pub fn octopus() -> Self {
         Enemy {
-            sprite_file_name: "octopus.png".into(),
+            kind: EnemyKind::Octopus,
             points: 10,
-            width: 24.,
-            height: 16.,
         }
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Debug)]
 pub struct EnemyPosition {
     pub x: u8,
     pub y: u8,
@@ -229,24 +309,19 @@ pub struct EnemyBundle {
     texture_atlas: TextureAtlas,
     pub position: EnemyPosition,
     hitable: Hitable,
-    size: Size,
+    collider: Collider,
     hitpoints: Hitpoints,
 }
 
 impl EnemyBundle {
-    pub fn new(
-        enemy: Enemy,
-        x: f32,
-        y: f32,
-        texture: Handle<Image>,
-        layout: Handle<TextureAtlasLayout>,
-    ) -> Self {
+    pub fn new(enemy: Enemy, x: f32, y: f32, atlas: &SpriteAtlas) -> Self {
         Self {
             marker: enemy.clone(),
             sprite: SpriteBundle {
-                texture,
+                texture: atlas.texture.clone(),
                 sprite: Sprite {
                     color: SPRITE_COLOR,
+                    custom_size: Some(Vec2::splat(SPRITE_SIZE)),
                     ..default()
                 },
                 transform: Transform {
@@ -255,13 +330,13 @@ impl EnemyBundle {
                 },
                 ..default()
             },
-            texture_atlas: TextureAtlas { layout, index: 0 },
+            texture_atlas: atlas.texture_atlas(enemy.kind.sprite_name()),
             position: EnemyPosition { x: 0, y: 0 },
-            hitable: Hitable,
-            size: Size {
-                width: enemy.width,
-                height: enemy.height,
+            hitable: Hitable {
+                kind: HitKind::Invader(enemy.kind),
             },
+            collider: Collider::new(SPRITE_SIZE, SPRITE_SIZE, CollisionLayer::Invader)
+                .pixel_perfect(),
             hitpoints: Hitpoints(enemy.points),
         }
     }
@@ -283,36 +358,31 @@ pub struct UfoBundle {
     texture_atlas: TextureAtlas,
     hitable: Hitable,
     direction: UfoDirection,
-    size: Size,
+    collider: Collider,
     hitpoints: Hitpoints,
 }
 
 impl UfoBundle {
-    pub fn new(
-        x: f32,
-        y: f32,
-        texture: Handle<Image>,
-        layout: Handle<TextureAtlasLayout>,
-        direction: f32,
-    ) -> Self {
+    pub fn new(x: f32, y: f32, atlas: &SpriteAtlas, direction: f32, rng: &mut GameRng) -> Self {
         let possible_hitpoints = [50, 100, 150, 200, 300];
-        let hp_index = thread_rng().gen_range(0..5);
+        let hp_index = rng.gen_range(0..5);
         let hitpoints = Hitpoints(possible_hitpoints[hp_index]);
 
         Self {
             marker: Ufo,
             sprite: SpriteBundle {
-                texture,
+                texture: atlas.texture.clone(),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(SPRITE_SIZE)),
+                    ..default()
+                },
                 transform: Transform::from_translation(Vec2::new(x, y).extend(0.)),
                 ..default()
             },
-            texture_atlas: TextureAtlas { layout, index: 0 },
-            hitable: Hitable,
+            texture_atlas: atlas.texture_atlas("ufo"),
+            hitable: Hitable { kind: HitKind::Ufo },
             direction: UfoDirection(direction),
-            size: Size {
-                width: 32.,
-                height: 14.,
-            },
+            collider: Collider::new(SPRITE_SIZE, SPRITE_SIZE, CollisionLayer::Ufo).pixel_perfect(),
             hitpoints,
         }
     }
@@ -324,20 +394,129 @@ pub struct LifesUI;
 #[derive(Component)]
 pub struct ScoreUI;
 
+#[derive(Resource, Deref, DerefMut)]
+pub struct BombTimer(pub Timer);
+
+// the buttons of a menu from top to bottom, and the one that is pressed by the keyboard
+// or a gamepad, which the mouse also moves to when hovering over a button
+#[derive(Component, Debug, Default)]
+pub struct Menu {
+    pub buttons: Vec<Entity>,
+    pub focus: usize,
+}
+
+impl Menu {
+    pub fn focused(&self) -> Option<Entity> {
+        self.buttons.get(self.focus).copied()
+    }
+
+    // wraps around at either end
+    pub fn move_focus(&mut self, by: isize) {
+        if !self.buttons.is_empty() {
+            let focus = self.focus as isize + by;
+            self.focus = focus.rem_euclid(self.buttons.len() as isize) as usize;
+        }
+    }
+}
+
+// the round being played, counting up from 1 for the whole game
 #[derive(Resource, Debug)]
-pub struct Difficulty(pub i32);
+pub struct Level(pub u32);
+
+const BONUS_TALLY_DURATION: Duration = Duration::from_millis(1500);
+const COUNTDOWN_SECONDS: u32 = 3;
+
+// the sequence between two levels, where the bonuses are tallied up into the score
+// before the next round counts down
+#[derive(Resource, Debug)]
+pub struct LevelTransition {
+    pub bonuses: Vec<(String, i32)>,
+    pub score_before: i32,
+    pub elapsed: Duration,
+}
+
+impl LevelTransition {
+    pub fn new(bonuses: Vec<(String, i32)>, score_before: i32) -> Self {
+        Self {
+            bonuses,
+            score_before,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn total_bonus(&self) -> i32 {
+        self.bonuses.iter().map(|(_, points)| points).sum()
+    }
+
+    // the score counting up from where it was by the bonuses
+    pub fn tallied_score(&self) -> i32 {
+        let tallied = (self.elapsed.as_secs_f32() / BONUS_TALLY_DURATION.as_secs_f32()).min(1.);
+        self.score_before + (self.total_bonus() as f32 * tallied) as i32
+    }
+
+    // the seconds left before the next round, once the bonuses are tallied up
+    pub fn countdown(&self) -> Option<u32> {
+        let counted = self.elapsed.checked_sub(BONUS_TALLY_DURATION)?;
+        Some(COUNTDOWN_SECONDS.saturating_sub(counted.as_secs() as u32))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.countdown() == Some(0)
+    }
+}
 
-impl Default for Difficulty {
+#[derive(Component, Clone, Copy, Debug)]
+pub enum LevelTransitionLine {
+    Round,
+    Bonus(usize),
+    Score,
+    Countdown,
+}
+
+// what a new game starts with, set from the command line
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct GameSettings {
+    pub seed: u64,
+    pub level: u8,
+    pub lifes: i8,
+    #[serde(default)]
+    pub difficulty: DifficultyPreset,
+    // nudges the difficulty to how well the game goes
+    #[serde(default)]
+    pub adaptive: bool,
+}
+
+impl Default for GameSettings {
     fn default() -> Self {
-        Self(100)
+        Self {
+            seed: random(),
+            level: 1,
+            lifes: DifficultyPreset::default().lifes(),
+            difficulty: DifficultyPreset::default(),
+            adaptive: false,
+        }
     }
 }
 
-#[derive(Component, Debug)]
-pub struct Menu;
+impl GameSettings {
+    // the same bounds the command line holds the settings to
+    pub fn check(&self) -> Result<(), String> {
+        if self.level < 1 {
+            return Err(format!("level {} is not at least 1", self.level));
+        }
+        if !(1..=9).contains(&self.lifes) {
+            return Err(format!("lifes {} is not in 1..=9", self.lifes));
+        }
+        Ok(())
+    }
+}
 
-#[derive(Component, Debug)]
-pub struct GameOverSign;
+// every random decision of the game is drawn from here, so a seed replays the same game
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(StdRng);
 
-#[derive(Resource, Debug)]
-pub struct Level(pub f32);
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{prelude::*, GameState};
+
+// the inner workings of the difficulty, toggled with F3 while playing
+#[derive(Resource, Debug, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+pub struct DebugOverlayRoot;
+
+#[derive(Component, Clone, Copy, Debug)]
+pub enum DebugOverlayLine {
+    Skill,
+    March,
+    Bombs,
+    Accuracy,
+}
+
+pub fn toggle_debug_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut debug_overlay: ResMut<DebugOverlay>,
+) {
+    if keys.just_pressed(KeyCode::F3) {
+        debug_overlay.enabled = !debug_overlay.enabled;
+        info!("Debug overlay enabled: {}", debug_overlay.enabled);
+    }
+}
+
+pub fn spawn_debug_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(TEXT_SIZE * 2.),
+                    right: Val::Px(0.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DebugOverlayRoot,
+            StateScoped(GameState::Playing),
+        ))
+        .with_children(|parent| {
+            for line in [
+                DebugOverlayLine::Skill,
+                DebugOverlayLine::March,
+                DebugOverlayLine::Bombs,
+                DebugOverlayLine::Accuracy,
+            ] {
+                parent.spawn((BitmapTextBundle::new("", TEXT_SIZE), line));
+            }
+        });
+}
+
+pub fn update_debug_overlay(
+    debug_overlay: Res<DebugOverlay>,
+    settings: Res<GameSettings>,
+    difficulty: Res<Difficulty>,
+    adaptive_difficulty: Res<AdaptiveDifficulty>,
+    player: Res<Player>,
+    mut root_query: Query<&mut Visibility, With<DebugOverlayRoot>>,
+    mut line_query: Query<(&DebugOverlayLine, &mut BitmapText)>,
+) {
+    for mut visibility in root_query.iter_mut() {
+        let wanted = match debug_overlay.enabled {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+
+    if !debug_overlay.enabled {
+        return;
+    }
+
+    for (line, mut text) in line_query.iter_mut() {
+        let value = match line {
+            DebugOverlayLine::Skill => match settings.adaptive {
+                true => format!("Skill {:+.2}", adaptive_difficulty.skill),
+                false => "Adaptive off".into(),
+            },
+            DebugOverlayLine::March => {
+                format!("March {}ms", difficulty.march_interval.as_millis())
+            }
+            DebugOverlayLine::Bombs => format!("Bombs {:.0}%", difficulty.bomb_chance * 100.),
+            DebugOverlayLine::Accuracy => {
+                format!("Accuracy {:.0}%", player.level_accuracy() * 100.)
+            }
+        };
+
+        if text.value != value {
+            text.value = value;
+        }
+    }
+}
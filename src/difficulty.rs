@@ -0,0 +1,324 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    // paced like the arcade, where the march speeds up with every invader shot
+    Arcade,
+}
+
+impl DifficultyPreset {
+    pub const ALL: [DifficultyPreset; 4] = [
+        DifficultyPreset::Easy,
+        DifficultyPreset::Normal,
+        DifficultyPreset::Hard,
+        DifficultyPreset::Arcade,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|preset| *preset == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn lifes(self) -> i8 {
+        Difficulty::from(self).lifes
+    }
+}
+
+impl fmt::Display for DifficultyPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DifficultyPreset::Easy => "Easy",
+            DifficultyPreset::Normal => "Normal",
+            DifficultyPreset::Hard => "Hard",
+            DifficultyPreset::Arcade => "Arcade",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for DifficultyPreset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.to_string().eq_ignore_ascii_case(name))
+            .ok_or(format!(
+                "expected one of easy, normal, hard or arcade, got {}",
+                name
+            ))
+    }
+}
+
+// how fast the invaders march and how often they drop bombs and send the UFO,
+// each timed on its own so one can be tuned without changing the others
+#[derive(Resource, Clone, Debug)]
+pub struct Difficulty {
+    pub march_interval: Duration,
+    // taken off the march interval whenever the invaders step down
+    pub march_speedup: Duration,
+    // taken off the march interval for every invader shot
+    pub kill_speedup: Duration,
+    pub min_march_interval: Duration,
+    pub bomb_interval: Duration,
+    // the chance of each bottom-most invader to drop a bomb when the bomb timer finishes
+    pub bomb_chance: f32,
+    // the most bombs falling at the same time
+    pub max_bombs: usize,
+    // in units per second
+    pub bomb_speed: f32,
+    pub ufo_interval: Duration,
+    pub lifes: i8,
+}
+
+impl Difficulty {
+    pub fn speed_up_march(&mut self, speedup: Duration) {
+        self.march_interval = self
+            .march_interval
+            .saturating_sub(speedup)
+            .max(self.min_march_interval);
+    }
+
+    // keeps every parameter within what the game can be played with
+    pub fn validated(self) -> Self {
+        let min_march_interval = clamped(
+            "min_march_interval",
+            self.min_march_interval,
+            Duration::from_millis(20),
+            Duration::from_secs(1),
+        );
+        let march_interval = clamped(
+            "march_interval",
+            self.march_interval,
+            min_march_interval,
+            Duration::from_secs(5),
+        );
+
+        Self {
+            march_interval,
+            march_speedup: clamped(
+                "march_speedup",
+                self.march_speedup,
+                Duration::ZERO,
+                march_interval,
+            ),
+            kill_speedup: clamped(
+                "kill_speedup",
+                self.kill_speedup,
+                Duration::ZERO,
+                march_interval,
+            ),
+            min_march_interval,
+            bomb_interval: clamped(
+                "bomb_interval",
+                self.bomb_interval,
+                Duration::from_millis(100),
+                Duration::from_secs(10),
+            ),
+            bomb_chance: clamped("bomb_chance", self.bomb_chance, 0., 1.),
+            max_bombs: clamped("max_bombs", self.max_bombs, 1, 10),
+            bomb_speed: clamped("bomb_speed", self.bomb_speed, 5., 200.),
+            ufo_interval: clamped(
+                "ufo_interval",
+                self.ufo_interval,
+                Duration::from_secs(1),
+                Duration::from_secs(120),
+            ),
+            lifes: clamped("lifes", self.lifes, 1, 9),
+        }
+    }
+}
+
+fn clamped<T: PartialOrd + fmt::Debug>(name: &str, value: T, min: T, max: T) -> T {
+    if value < min {
+        warn!("Difficulty {} of {:?} raised to {:?}", name, value, min);
+        min
+    } else if value > max {
+        warn!("Difficulty {} of {:?} lowered to {:?}", name, value, max);
+        max
+    } else {
+        value
+    }
+}
+
+impl From<DifficultyPreset> for Difficulty {
+    fn from(preset: DifficultyPreset) -> Self {
+        let difficulty = match preset {
+            DifficultyPreset::Easy => Self {
+                march_interval: Duration::from_millis(1200),
+                march_speedup: Duration::from_millis(50),
+                kill_speedup: Duration::ZERO,
+                min_march_interval: Duration::from_millis(100),
+                bomb_interval: Duration::from_millis(1500),
+                bomb_chance: 0.05,
+                max_bombs: 2,
+                bomb_speed: 25.,
+                ufo_interval: Duration::from_secs(15),
+                lifes: 5,
+            },
+            DifficultyPreset::Normal => Self {
+                march_interval: Duration::from_millis(1000),
+                march_speedup: Duration::from_millis(70),
+                kill_speedup: Duration::ZERO,
+                min_march_interval: Duration::from_millis(20),
+                bomb_interval: Duration::from_millis(1000),
+                bomb_chance: 0.1,
+                max_bombs: 3,
+                bomb_speed: 30.,
+                ufo_interval: Duration::from_secs(10),
+                lifes: 3,
+            },
+            DifficultyPreset::Hard => Self {
+                march_interval: Duration::from_millis(800),
+                march_speedup: Duration::from_millis(80),
+                kill_speedup: Duration::from_millis(8),
+                min_march_interval: Duration::from_millis(20),
+                bomb_interval: Duration::from_millis(700),
+                bomb_chance: 0.15,
+                max_bombs: 4,
+                bomb_speed: 40.,
+                ufo_interval: Duration::from_secs(8),
+                lifes: 3,
+            },
+            DifficultyPreset::Arcade => Self {
+                march_interval: Duration::from_millis(900),
+                march_speedup: Duration::from_millis(40),
+                kill_speedup: Duration::from_millis(15),
+                min_march_interval: Duration::from_millis(20),
+                bomb_interval: Duration::from_millis(800),
+                bomb_chance: 0.12,
+                max_bombs: 3,
+                bomb_speed: 35.,
+                ufo_interval: Duration::from_secs(25),
+                lifes: 3,
+            },
+        };
+
+        difficulty.validated()
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        DifficultyPreset::default().into()
+    }
+}
+
+// how far the adaptive mode may nudge the bomb chance and the march interval of
+// the preset, as a share of their value
+const MAX_BOMB_CHANCE_NUDGE: f32 = 0.5;
+const MAX_MARCH_INTERVAL_NUDGE: f32 = 0.25;
+const SKILL_STEP: f32 = 0.1;
+
+// how well the game goes, from -1 when the preset is far too hard to 1 when it is
+// far too easy, which the difficulty is nudged by when playing adaptive
+#[derive(Resource, Debug, Default)]
+pub struct AdaptiveDifficulty {
+    pub skill: f32,
+    level_started_at: Duration,
+    deaths_this_level: u32,
+}
+
+impl AdaptiveDifficulty {
+    // both stay within the bounds of the preset, whatever the skill
+    pub fn apply(&self, difficulty: &mut Difficulty) {
+        difficulty.bomb_chance =
+            (difficulty.bomb_chance * (1. + MAX_BOMB_CHANCE_NUDGE * self.skill)).clamp(0., 1.);
+        difficulty.march_interval = difficulty
+            .march_interval
+            .mul_f32(1. - MAX_MARCH_INTERVAL_NUDGE * self.skill)
+            .max(difficulty.min_march_interval);
+    }
+
+    fn nudge(&mut self, by: f32, reason: &str) {
+        self.skill = (self.skill + by).clamp(-1., 1.);
+        info!(
+            "Adaptive difficulty nudged by {:+.2} to {:+.2}, {}",
+            by, self.skill, reason
+        );
+    }
+}
+
+pub fn adaptive_difficulty_enabled(settings: Res<GameSettings>) -> bool {
+    settings.adaptive
+}
+
+pub fn start_adaptive_level(
+    time: Res<Time>,
+    mut adaptive_difficulty: ResMut<AdaptiveDifficulty>,
+    mut level_started_event_reader: EventReader<LevelStarted>,
+) {
+    for _ in level_started_event_reader.read() {
+        adaptive_difficulty.level_started_at = time.elapsed();
+        adaptive_difficulty.deaths_this_level = 0;
+    }
+}
+
+// dying makes the bombs a little rarer right away
+pub fn adapt_to_deaths(
+    settings: Res<GameSettings>,
+    mut adaptive_difficulty: ResMut<AdaptiveDifficulty>,
+    mut difficulty: ResMut<Difficulty>,
+    mut player_hit_event_reader: EventReader<PlayerHitEvent>,
+) {
+    for _ in player_hit_event_reader.read() {
+        adaptive_difficulty.deaths_this_level += 1;
+        adaptive_difficulty.nudge(-SKILL_STEP / 2., "after dying");
+
+        let mut adapted = Difficulty::from(settings.difficulty);
+        adaptive_difficulty.apply(&mut adapted);
+        difficulty.bomb_chance = adapted.bomb_chance;
+    }
+}
+
+// the march speed is only nudged for the next level, along with the bombs
+pub fn adapt_to_cleared_level(
+    time: Res<Time>,
+    player: Res<Player>,
+    mut adaptive_difficulty: ResMut<AdaptiveDifficulty>,
+    mut level_cleared_event_reader: EventReader<LevelCleared>,
+) {
+    for _ in level_cleared_event_reader.read() {
+        let accuracy = player.level_accuracy();
+        let deaths = adaptive_difficulty.deaths_this_level;
+        let time_to_clear = time.elapsed() - adaptive_difficulty.level_started_at;
+
+        let mut nudge = 0.;
+        if accuracy > 0.6 {
+            nudge += SKILL_STEP;
+        } else if accuracy < 0.3 {
+            nudge -= SKILL_STEP;
+        }
+        match deaths {
+            0 => nudge += SKILL_STEP,
+            1 => {}
+            _ => nudge -= SKILL_STEP,
+        }
+        if time_to_clear < Duration::from_secs(60) {
+            nudge += SKILL_STEP;
+        } else if time_to_clear > Duration::from_secs(150) {
+            nudge -= SKILL_STEP;
+        }
+
+        adaptive_difficulty.nudge(
+            nudge,
+            &format!(
+                "after clearing the level with {:.0}% accuracy and {} deaths in {:.0}s",
+                accuracy * 100.,
+                deaths,
+                time_to_clear.as_secs_f32()
+            ),
+        );
+    }
+}
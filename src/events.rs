@@ -0,0 +1,89 @@
+use crate::prelude::*;
+
+// what a laser or bomb can hit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitKind {
+    Invader(EnemyKind),
+    Ufo,
+    Bomb,
+}
+
+// who fired a laser or dropped a bomb
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shooter {
+    Cannon,
+    Invader(EnemyKind),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Projectile {
+    Laser,
+    Bomb,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOverCause {
+    NoLifesLeft,
+    InvadersLanded,
+}
+
+#[derive(Event, Debug)]
+pub struct ShotFired {
+    pub shooter: Shooter,
+    pub projectile: Projectile,
+    pub position: Vec2,
+}
+
+// something was shot by the cannon
+#[derive(Event, Debug)]
+pub struct HitEvent {
+    pub entity: Entity,
+    pub kind: HitKind,
+    pub position: Vec2,
+    pub points: i32,
+    pub shooter: Shooter,
+    pub cause: Projectile,
+}
+
+#[derive(Event, Debug)]
+pub struct PlayerHitEvent {
+    pub position: Vec2,
+    pub shooter: Shooter,
+    pub cause: Projectile,
+}
+
+// the invaders reached a wall and step down on the next tick
+#[derive(Event, Debug)]
+pub struct EnemyAdvancement {
+    pub direction: f32,
+}
+
+#[derive(Event, Debug)]
+pub struct UfoSpawned {
+    pub position: Vec2,
+    pub direction: f32,
+}
+
+// the UFO left the screen without being shot
+#[derive(Event, Debug)]
+pub struct UfoEscaped {
+    pub position: Vec2,
+}
+
+#[derive(Event, Debug)]
+pub struct LevelStarted {
+    pub level: u32,
+}
+
+#[derive(Event, Debug)]
+pub struct LevelCleared {
+    pub level: u32,
+    pub score: i32,
+}
+
+#[derive(Event, Debug)]
+pub struct GameOverEvent {
+    pub cause: GameOverCause,
+    pub level: u32,
+    pub score: i32,
+}
@@ -0,0 +1,180 @@
+use crate::prelude::*;
+
+// the glyphs are named font_<character> in the sprite atlas, which only has upper case letters
+pub fn glyph_index(atlas: &SpriteAtlas, character: char) -> Option<usize> {
+    atlas.index(&format!("font_{}", character.to_ascii_uppercase()))
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct BitmapText {
+    pub value: String,
+    pub size: f32,
+    // falls back to the text color of the theme
+    pub color: Option<Color>,
+}
+
+impl BitmapText {
+    pub fn new(value: impl Into<String>, size: f32) -> Self {
+        Self {
+            value: value.into(),
+            size,
+            color: None,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BitmapGlyph;
+
+#[derive(Bundle)]
+pub struct BitmapTextBundle {
+    text: BitmapText,
+    node: NodeBundle,
+}
+
+impl BitmapTextBundle {
+    pub fn new(value: impl Into<String>, size: f32) -> Self {
+        Self {
+            text: BitmapText::new(value, size),
+            node: NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            },
+        }
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.node.style = Style {
+            flex_direction: FlexDirection::Row,
+            ..style
+        };
+        self
+    }
+}
+
+// rebuilds the glyphs of every text that changed, either as UI images when the text
+// lives in the UI tree or as sprites laid out to the right of its transform otherwise
+pub fn render_bitmap_text(
+    mut commands: Commands,
+    atlas: Res<SpriteAtlas>,
+    themes: Res<Themes>,
+    text_query: Query<(Entity, &BitmapText, Has<Node>), Changed<BitmapText>>,
+) {
+    for (entity, text, is_ui) in text_query.iter() {
+        let color = text.color.unwrap_or(themes.current().text_color);
+        commands.entity(entity).despawn_descendants();
+
+        commands.entity(entity).with_children(|parent| {
+            for (i, character) in text.value.chars().enumerate() {
+                let glyph = glyph_index(&atlas, character);
+
+                if is_ui {
+                    let size = Style {
+                        width: Val::Px(text.size),
+                        height: Val::Px(text.size),
+                        ..default()
+                    };
+
+                    match glyph {
+                        Some(index) => parent.spawn((
+                            ImageBundle {
+                                style: size,
+                                image: UiImage::new(atlas.texture.clone()).with_color(color),
+                                ..default()
+                            },
+                            TextureAtlas {
+                                layout: atlas.layout.clone(),
+                                index,
+                            },
+                            BitmapGlyph,
+                        )),
+                        None => parent.spawn((
+                            NodeBundle {
+                                style: size,
+                                ..default()
+                            },
+                            BitmapGlyph,
+                        )),
+                    };
+                } else if let Some(index) = glyph {
+                    parent.spawn((
+                        SpriteBundle {
+                            texture: atlas.texture.clone(),
+                            sprite: Sprite {
+                                color,
+                                custom_size: Some(Vec2::splat(text.size)),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz((i as f32 + 0.5) * text.size, 0., 0.),
+                            ..default()
+                        },
+                        TextureAtlas {
+                            layout: atlas.layout.clone(),
+                            index,
+                        },
+                        BitmapGlyph,
+                    ));
+                }
+            }
+        });
+    }
+}
+
+// how long the typewriter takes for each character, about as fast as the arcade prints
+const TYPEWRITER_CHARACTER: Duration = Duration::from_millis(80);
+
+// types a text out one character at a time, starting after a delay
+#[derive(Component, Debug)]
+pub struct Typewriter {
+    text: String,
+    delay: Duration,
+    elapsed: Duration,
+}
+
+impl Typewriter {
+    pub fn new(text: impl Into<String>, delay: Duration) -> Self {
+        Self {
+            text: text.into(),
+            delay,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // how long typing the whole text takes, to start the next one after it
+    pub fn duration(&self) -> Duration {
+        TYPEWRITER_CHARACTER * self.text.chars().count() as u32
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.delay + self.duration()
+    }
+}
+
+// whether every text being typed out is there in full
+pub fn typed_out(typewriter_query: Query<&Typewriter>) -> bool {
+    typewriter_query.iter().all(Typewriter::is_finished)
+}
+
+// on the real clock, so it also types while the game is paused
+pub fn type_out_text(
+    time: Res<Time<Real>>,
+    mut text_query: Query<(&mut Typewriter, &mut BitmapText)>,
+) {
+    for (mut typewriter, mut text) in text_query.iter_mut() {
+        typewriter.elapsed += time.delta();
+
+        let typed = typewriter
+            .elapsed
+            .saturating_sub(typewriter.delay)
+            .as_millis()
+            / TYPEWRITER_CHARACTER.as_millis();
+        let value: String = typewriter.text.chars().take(typed as usize).collect();
+
+        if text.value != value {
+            text.value = value;
+        }
+    }
+}
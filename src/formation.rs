@@ -0,0 +1,187 @@
+use crate::prelude::*;
+
+pub const FORMATION_COLUMNS: usize = 11;
+pub const FORMATION_ROWS: usize = 5;
+
+// the distance between the centers of two neighbouring invaders
+const CELL_SPACING: f32 = SPRITE_SIZE + 1.;
+
+// how far below the first round the invaders start in each round, and the entry the
+// table starts over from once every round in it has been played
+#[derive(Resource, Clone, Debug)]
+pub struct StartingHeights {
+    drops: Vec<f32>,
+    wrap_to: usize,
+}
+
+impl StartingHeights {
+    pub fn new(drops: Vec<f32>, wrap_to: usize) -> Result<Self, String> {
+        if drops.is_empty() {
+            return Err("the table of starting heights is empty".into());
+        }
+        if wrap_to >= drops.len() {
+            return Err(format!(
+                "the table of {} starting heights can't wrap to entry {}",
+                drops.len(),
+                wrap_to
+            ));
+        }
+
+        Ok(Self { drops, wrap_to })
+    }
+
+    pub fn index(&self, round: u32) -> usize {
+        let index = round.saturating_sub(1) as usize;
+        if index < self.drops.len() {
+            return index;
+        }
+
+        let wrapped = self.drops.len() - self.wrap_to;
+        self.wrap_to + (index - self.drops.len()) % wrapped
+    }
+
+    pub fn drop(&self, round: u32) -> f32 {
+        self.drops[self.index(round)]
+    }
+}
+
+impl Default for StartingHeights {
+    // the heights of the bottom row in the arcade, where the first round starts at 0x78
+    // and the table wraps back to the height of the second round after the ninth
+    fn default() -> Self {
+        let heights: [u8; 9] = [0x78, 0x60, 0x50, 0x48, 0x48, 0x48, 0x40, 0x40, 0x40];
+
+        Self {
+            drops: heights
+                .iter()
+                .map(|height| (heights[0] - height) as f32)
+                .collect(),
+            wrap_to: 1,
+        }
+    }
+}
+
+// the grid the invaders march in, kept up to date as they are spawned and shot,
+// so nothing has to look through all of them to find the edges or the shooters
+#[derive(Resource, Debug)]
+pub struct Formation {
+    // the center of the top left cell, whether its invader is still alive or not
+    pub origin: Vec2,
+    cells: [[Option<Entity>; FORMATION_ROWS]; FORMATION_COLUMNS],
+    remaining: usize,
+}
+
+impl Formation {
+    pub fn new(origin: Vec2) -> Self {
+        Self {
+            origin,
+            cells: [[None; FORMATION_ROWS]; FORMATION_COLUMNS],
+            remaining: 0,
+        }
+    }
+
+    pub fn insert(&mut self, position: &EnemyPosition, entity: Entity) {
+        let cell = &mut self.cells[position.x as usize][position.y as usize];
+        if cell.replace(entity).is_none() {
+            self.remaining += 1;
+        }
+    }
+
+    // returns whether the invader was part of the formation
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        for cell in self.cells.iter_mut().flatten() {
+            if *cell == Some(entity) {
+                *cell = None;
+                self.remaining -= 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn cell_position(&self, position: &EnemyPosition) -> Vec2 {
+        self.origin
+            + Vec2::new(
+                position.x as f32 * CELL_SPACING,
+                position.y as f32 * -CELL_SPACING,
+            )
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    pub fn alive_in_column(&self, column: usize) -> usize {
+        self.cells[column].iter().flatten().count()
+    }
+
+    pub fn alive_in_row(&self, row: usize) -> usize {
+        self.cells
+            .iter()
+            .filter(|column| column[row].is_some())
+            .count()
+    }
+
+    // the bottom-most invader of a column, which is the only one that drops bombs
+    pub fn shooter(&self, column: usize) -> Option<Entity> {
+        self.cells[column].iter().rev().flatten().next().copied()
+    }
+
+    pub fn shooters(&self) -> impl Iterator<Item = Entity> + '_ {
+        (0..FORMATION_COLUMNS).filter_map(|column| self.shooter(column))
+    }
+
+    // the area covered by the sprites of the invaders still alive
+    pub fn bounds(&self) -> Option<Rect> {
+        let columns = (0..FORMATION_COLUMNS).filter(|&column| self.alive_in_column(column) > 0);
+        let rows = (0..FORMATION_ROWS).filter(|&row| self.alive_in_row(row) > 0);
+
+        let (left, right) = (columns.clone().min()?, columns.max()?);
+        let (top, bottom) = (rows.clone().min()?, rows.max()?);
+
+        let top_left = self.cell_position(&EnemyPosition {
+            x: left as u8,
+            y: top as u8,
+        });
+        let bottom_right = self.cell_position(&EnemyPosition {
+            x: right as u8,
+            y: bottom as u8,
+        });
+
+        Some(Rect::from_corners(top_left, bottom_right).inflate(SPRITE_SIZE / 2.))
+    }
+}
+
+pub fn remove_shot_invaders(
+    mut formation: ResMut<Formation>,
+    mut hit_event_reader: EventReader<HitEvent>,
+) {
+    for hit in hit_event_reader.read() {
+        if let HitKind::Invader(_) = hit.kind {
+            formation.remove(hit.entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starting_heights_wrap_after_the_ninth_round() {
+        let starting_heights = StartingHeights::default();
+
+        assert_eq!(starting_heights.index(9), 8);
+        assert_eq!(starting_heights.index(10), 1);
+        assert_eq!(starting_heights.index(17), 8);
+        assert_eq!(starting_heights.index(18), 1);
+        assert_eq!(starting_heights.drop(10), starting_heights.drop(2));
+    }
+
+    #[test]
+    fn starting_heights_reject_tables_they_cant_wrap() {
+        assert!(StartingHeights::new(Vec::new(), 0).is_err());
+        assert!(StartingHeights::new(vec![0., 8.], 2).is_err());
+        assert!(StartingHeights::new(vec![0., 8.], 1).is_ok());
+    }
+}
@@ -0,0 +1,73 @@
+use std::{fs, path::PathBuf};
+
+use crate::prelude::*;
+
+// how many scores the table keeps, like the arcade's top ten
+pub const MAX_HIGH_SCORES: usize = 10;
+
+// kept next to where the game is started from
+pub const HIGH_SCORES_FILE: &str = "highscores.ron";
+
+// the best scores so far, highest first, which are only kept in memory unless the
+// table was loaded from a file
+#[derive(Resource, Default, Debug)]
+pub struct HighScores {
+    scores: Vec<i32>,
+    path: Option<PathBuf>,
+}
+
+impl HighScores {
+    // starts an empty table if the file doesn't exist yet or can't be read
+    pub fn load(path: PathBuf) -> Self {
+        let scores = match fs::read_to_string(&path) {
+            Ok(scores) => ron::from_str(&scores).unwrap_or_else(|error| {
+                warn!("Could not read high scores {}: {}", path.display(), error);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            scores,
+            path: Some(path),
+        }
+    }
+
+    // keeps the high scores in the file between runs
+    pub fn install(app: &mut App, path: PathBuf) {
+        app.insert_resource(Self::load(path));
+    }
+
+    pub fn scores(&self) -> &[i32] {
+        &self.scores
+    }
+
+    // returns the rank the score made it to, starting at 1, if it made the table at all
+    pub fn insert(&mut self, score: i32) -> Option<usize> {
+        let index = self.scores.iter().position(|best| score > *best);
+        let index = match index {
+            Some(index) => index,
+            None if self.scores.len() < MAX_HIGH_SCORES => self.scores.len(),
+            None => return None,
+        };
+
+        self.scores.insert(index, score);
+        self.scores.truncate(MAX_HIGH_SCORES);
+        self.save();
+
+        Some(index + 1)
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let result = ron::to_string(&self.scores)
+            .map_err(|error| error.to_string())
+            .and_then(|scores| fs::write(path, scores).map_err(|error| error.to_string()));
+        if let Err(error) = result {
+            warn!("Could not save high scores {}: {}", path.display(), error);
+        }
+    }
+}
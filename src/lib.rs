@@ -0,0 +1,83 @@
+pub mod asset_files;
+pub mod atlas;
+pub mod collision;
+pub mod components;
+pub mod debug_overlay;
+pub mod difficulty;
+pub mod events;
+pub mod font;
+pub mod formation;
+pub mod high_scores;
+pub mod loading;
+pub mod overlay;
+pub mod plugins;
+pub mod replay;
+pub mod screen;
+pub mod sounds;
+pub mod spawners;
+pub mod systems;
+pub mod theme;
+
+// everything needed to build on the game, `use invader::prelude::*;`
+pub mod prelude {
+    // the native resolution of the arcade, which the window is scaled up from
+    pub const SCREEN_WIDTH: f32 = 224.;
+    pub const SCREEN_HEIGHT: f32 = 256.;
+    pub const WINDOW_SCALE: f32 = 2.;
+    pub const SPRITE_COLOR: Color = Color::srgb(1., 1., 1.);
+    pub const TEXT_COLOR: Color = Color::srgb(1., 1., 1.);
+    pub const TEXT_SIZE: f32 = 8.;
+    pub const TOP_MENU_HEIGHT: f32 = 24.;
+    pub const BOTTOM_MENU_HEIGHT: f32 = 16.;
+    pub const TOP_WALL: f32 = (SCREEN_HEIGHT / 2.) - TOP_MENU_HEIGHT;
+    pub const RIGHT_WALL: f32 = SCREEN_WIDTH / 2.;
+    pub const BOTTOM_WALL: f32 = SCREEN_HEIGHT / -2. + BOTTOM_MENU_HEIGHT;
+    pub const LEFT_WALL: f32 = SCREEN_WIDTH / -2.;
+    pub const SPRITE_SIZE: f32 = 16.;
+
+    pub use crate::asset_files::*;
+    pub use crate::atlas::*;
+    pub use crate::collision::*;
+    pub use crate::components::*;
+    pub use crate::debug_overlay::*;
+    pub use crate::difficulty::*;
+    pub use crate::events::*;
+    pub use crate::font::*;
+    pub use crate::formation::*;
+    pub use crate::high_scores::*;
+    pub use crate::loading::*;
+    pub use crate::overlay::*;
+    pub use crate::plugins::*;
+    pub use crate::replay::*;
+    pub use crate::screen::*;
+    pub use crate::sounds::*;
+    pub use crate::spawners::*;
+    pub use crate::systems::*;
+    pub use crate::theme::*;
+    pub use bevy::prelude::*;
+    pub use rand::prelude::random;
+    pub use std::time::Duration;
+}
+
+use prelude::*;
+
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Loading,
+    AssetError,
+    Menu,
+    Playing,
+    LevelComplete,
+    GameOver,
+    Options,
+}
+
+// whether the game is paused, which only exists while a level is being played
+#[derive(SubStates, Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Playing)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
@@ -0,0 +1,213 @@
+use bevy::asset::{LoadState, UntypedAssetId};
+
+use crate::{prelude::*, GameState};
+
+#[derive(Resource)]
+pub struct GameAssets {
+    pub sprite_texture: Handle<Image>,
+    pub sprite_regions: Handle<SpriteRegions>,
+    pub fire_laser_sound: Handle<AudioSource>,
+    pub invader_killed_sound: Handle<AudioSource>,
+    pub invader_notes: [Handle<AudioSource>; 4],
+}
+
+impl GameAssets {
+    fn ids(&self) -> Vec<UntypedAssetId> {
+        [&self.fire_laser_sound, &self.invader_killed_sound]
+            .into_iter()
+            .chain(self.invader_notes.iter())
+            .map(|sound| sound.id().untyped())
+            .chain([
+                self.sprite_texture.id().untyped(),
+                self.sprite_regions.id().untyped(),
+            ])
+            .collect()
+    }
+}
+
+// the state to continue with once every asset is loaded
+#[derive(Resource)]
+pub struct AfterLoading(pub GameState);
+
+impl Default for AfterLoading {
+    fn default() -> Self {
+        Self(GameState::Menu)
+    }
+}
+
+impl AfterLoading {
+    // goes right into the game once loaded, without showing the menu first
+    pub fn skip_menu(app: &mut App) {
+        app.insert_resource(AfterLoading(GameState::Playing))
+            .add_systems(Startup, setup_player);
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct MissingAssets(pub Vec<String>);
+
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingProgressBar;
+
+pub fn load_assets(mut commands: Commands, asset_server: Res<AssetServer>, themes: Res<Themes>) {
+    let theme = themes.current();
+
+    let sound = |path: &str| asset_server.load(theme.asset_path(path));
+
+    commands.insert_resource(GameAssets {
+        // a theme may replace the texture independently of the regions
+        sprite_texture: asset_server.load(theme.asset_path("sprites.png")),
+        sprite_regions: asset_server.load(theme.asset_path("sprites.atlas.ron")),
+        fire_laser_sound: sound("sounds/shoot.ogg"),
+        invader_killed_sound: sound("sounds/invaderkilled.ogg"),
+        invader_notes: [
+            sound("sounds/fastinvader0.ogg"),
+            sound("sounds/fastinvader1.ogg"),
+            sound("sounds/fastinvader2.ogg"),
+            sound("sounds/fastinvader3.ogg"),
+        ],
+    });
+}
+
+pub fn check_assets_loaded(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_assets: Res<GameAssets>,
+    sprite_regions: Res<Assets<SpriteRegions>>,
+    after_loading: Res<AfterLoading>,
+    mut progress_bar_query: Query<&mut Style, With<LoadingProgressBar>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let ids = game_assets.ids();
+    let mut loaded = 0;
+    let mut missing = Vec::new();
+
+    for id in ids.iter() {
+        match asset_server.load_state(*id) {
+            LoadState::Loaded => loaded += 1,
+            LoadState::Failed(_) => missing.push(
+                asset_server
+                    .get_path(*id)
+                    .map_or("unknown asset".into(), |path| path.to_string()),
+            ),
+            _ => {}
+        }
+    }
+
+    if let Ok(mut style) = progress_bar_query.get_single_mut() {
+        style.width = Val::Percent(100. * loaded as f32 / ids.len() as f32);
+    }
+
+    if !missing.is_empty() {
+        error!("Failed to load {:?}", missing);
+        commands.insert_resource(MissingAssets(missing));
+        next_state.set(GameState::AssetError);
+    } else if loaded == ids.len() {
+        info!("Loaded {} assets", loaded);
+        if let Some(regions) = sprite_regions.get(&game_assets.sprite_regions) {
+            commands.insert_resource(SpriteAtlas::new(
+                game_assets.sprite_texture.clone(),
+                regions,
+            ));
+        }
+        next_state.set(after_loading.0.clone());
+    }
+}
+
+// the loading and error screens use the built-in font, as the bitmap font may be
+// the very asset that is still loading or missing
+pub fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LoadingScreen,
+            StateScoped(GameState::Loading),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "LOADING",
+                TextStyle {
+                    font_size: TEXT_SIZE * 2.,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(SCREEN_WIDTH / 2.),
+                        height: Val::Px(4.),
+                        margin: UiRect::top(Val::Px(TEXT_SIZE)),
+                        ..default()
+                    },
+                    background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.),
+                                height: Val::Percent(100.),
+                                ..default()
+                            },
+                            background_color: TEXT_COLOR.into(),
+                            ..default()
+                        },
+                        LoadingProgressBar,
+                    ));
+                });
+        });
+}
+
+pub fn spawn_asset_error_screen(mut commands: Commands, missing_assets: Res<MissingAssets>) {
+    let text_style = TextStyle {
+        font_size: TEXT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LoadingScreen,
+            StateScoped(GameState::AssetError),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Failed to load assets:",
+                TextStyle {
+                    color: Color::srgb(1., 0.25, 0.25),
+                    ..text_style.clone()
+                },
+            ));
+
+            for path in missing_assets.0.iter() {
+                parent.spawn(TextBundle::from_section(path.clone(), text_style.clone()));
+            }
+        });
+}
@@ -0,0 +1,105 @@
+use crate::prelude::*;
+
+// a horizontal strip of colored cellophane, covering the world y-coordinates from..to
+#[derive(Clone, Debug)]
+pub struct OverlayBand {
+    pub from: f32,
+    pub to: f32,
+    pub color: Color,
+}
+
+#[derive(Resource, Debug)]
+pub struct Overlay {
+    pub enabled: bool,
+    pub bands: Vec<OverlayBand>,
+}
+
+impl Overlay {
+    pub fn color_at(&self, y: f32) -> Option<Color> {
+        if !self.enabled {
+            return None;
+        }
+
+        self.bands
+            .iter()
+            .find(|band| band.from <= y && y < band.to)
+            .map(|band| band.color)
+    }
+}
+
+impl Default for Overlay {
+    // the layout of the arcade cabinet: red across the UFO, green over the cannon and lifes
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bands: vec![
+                OverlayBand {
+                    from: TOP_WALL - SPRITE_SIZE,
+                    to: TOP_WALL,
+                    color: Color::srgb(1., 0.25, 0.25),
+                },
+                OverlayBand {
+                    from: SCREEN_HEIGHT / -2.,
+                    to: BOTTOM_WALL + SPRITE_SIZE * 2.,
+                    color: Color::srgb(0.25, 1., 0.25),
+                },
+            ],
+        }
+    }
+}
+
+pub fn toggle_overlay(keys: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<Overlay>) {
+    if keys.just_pressed(KeyCode::F2) {
+        overlay.enabled = !overlay.enabled;
+        info!("Overlay enabled: {}", overlay.enabled);
+    }
+}
+
+pub fn tint_sprites(
+    overlay: Res<Overlay>,
+    themes: Res<Themes>,
+    mut sprite_query: Query<(&GlobalTransform, &mut Sprite), Without<BitmapGlyph>>,
+    mut glyph_sprite_query: Query<(&Parent, &GlobalTransform, &mut Sprite), With<BitmapGlyph>>,
+    mut glyph_image_query: Query<(&Parent, &GlobalTransform, &mut UiImage), With<BitmapGlyph>>,
+    text_query: Query<&BitmapText>,
+) {
+    let theme = themes.current();
+
+    for (transform, mut sprite) in sprite_query.iter_mut() {
+        let color = overlay
+            .color_at(transform.translation().y)
+            .unwrap_or(theme.sprite_color);
+
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+
+    // glyphs fall back to the color of their text instead
+    for (parent, transform, mut sprite) in glyph_sprite_query.iter_mut() {
+        let Ok(text) = text_query.get(parent.get()) else {
+            continue;
+        };
+        let color = overlay
+            .color_at(transform.translation().y)
+            .unwrap_or(text.color.unwrap_or(theme.text_color));
+
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+
+    // UI nodes are laid out from the top of the screen downwards
+    for (parent, transform, mut image) in glyph_image_query.iter_mut() {
+        let Ok(text) = text_query.get(parent.get()) else {
+            continue;
+        };
+        let color = overlay
+            .color_at(SCREEN_HEIGHT / 2. - transform.translation().y)
+            .unwrap_or(text.color.unwrap_or(theme.text_color));
+
+        if image.color != color {
+            image.color = color;
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use bevy::app::PluginGroupBuilder;
+
+use crate::{prelude::*, GameState, PauseState};
+
+// every plugin of the game, any of which can be disabled or replaced
+pub struct GamePlugins;
+
+impl PluginGroup for GamePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(CoreRulesPlugin)
+            .add(LoadingPlugin)
+            .add(ScreenPlugin)
+            .add(MenuPlugin)
+            .add(PausePlugin)
+            .add(PlayerPlugin)
+            .add(FormationPlugin)
+            .add(CollisionPlugin)
+            .add(ProjectilesPlugin)
+            .add(UfoPlugin)
+            .add(HudPlugin)
+            .add(AdaptiveDifficultyPlugin)
+            .add(DebugOverlayPlugin)
+            .add(SoundPlugin)
+            .add(ReplayPlugin)
+    }
+}
+
+// the rate of the simulation tick running FixedUpdate, which never changes
+const TICKS_PER_SECOND: f64 = 60.;
+
+// the stages of a frame, which run in this order in both Update and FixedUpdate
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    Input,
+    Movement,
+    Collision,
+    Scoring,
+    Rules,
+    Presentation,
+}
+
+// the states of the game and the rules for starting and ending levels and games
+pub struct CoreRulesPlugin;
+
+impl Plugin for CoreRulesPlugin {
+    fn build(&self, app: &mut App) {
+        // the settings may already have been set up by whoever embeds the game
+        app.init_resource::<GameSettings>();
+        let seed = app.world().resource::<GameSettings>().seed;
+
+        let stages = || {
+            (
+                GameSet::Input,
+                GameSet::Movement,
+                GameSet::Collision,
+                GameSet::Scoring,
+                GameSet::Rules,
+                GameSet::Presentation,
+            )
+                .chain()
+        };
+
+        app.add_event::<LevelStarted>()
+            .add_event::<LevelCleared>()
+            .add_event::<GameOverEvent>()
+            .configure_sets(Update, stages())
+            .configure_sets(FixedUpdate, stages())
+            .insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND))
+            .init_resource::<Difficulty>()
+            .init_resource::<StartingHeights>()
+            .init_state::<GameState>()
+            .add_sub_state::<PauseState>()
+            .enable_state_scoped_entities::<GameState>()
+            .enable_state_scoped_entities::<PauseState>()
+            .insert_resource(GameRng::new(seed))
+            .add_systems(OnEnter(GameState::Playing), (reset, discard_fixed_overstep))
+            .add_systems(
+                OnEnter(GameState::LevelComplete),
+                (start_next_level, spawn_level_transition).chain(),
+            )
+            .add_systems(
+                Update,
+                (check_game_over, check_level_complete)
+                    .chain()
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    advance_level_transition.in_set(GameSet::Rules),
+                    update_level_transition_ui.in_set(GameSet::Presentation),
+                )
+                    .run_if(in_state(GameState::LevelComplete)),
+            );
+    }
+}
+
+// loads the assets of the selected theme before anything else
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<SpriteRegions>()
+            .init_asset_loader::<SpriteAtlasLoader>()
+            .insert_resource(Themes::discover())
+            .init_resource::<AfterLoading>()
+            .add_systems(
+                OnEnter(GameState::Loading),
+                (load_assets, spawn_loading_screen),
+            )
+            .add_systems(OnExit(GameState::Loading), apply_theme)
+            .add_systems(OnEnter(GameState::AssetError), spawn_asset_error_screen)
+            .add_systems(
+                Update,
+                check_assets_loaded
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(GameState::Loading)),
+            );
+    }
+}
+
+// the camera, the bitmap text and the colored overlay
+pub struct ScreenPlugin;
+
+impl Plugin for ScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Overlay>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(
+                Update,
+                (toggle_fullscreen, toggle_overlay).in_set(GameSet::Input),
+            )
+            .add_systems(
+                Update,
+                (
+                    (fit_camera_to_window, type_out_text).in_set(GameSet::Presentation),
+                    // after the texts have been updated for this frame
+                    render_bitmap_text
+                        .after(GameSet::Presentation)
+                        .run_if(resource_exists::<SpriteAtlas>),
+                ),
+            )
+            // tint after the transforms have been propagated for this frame
+            .add_systems(
+                PostUpdate,
+                tint_sprites.after(bevy::transform::TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HighScores>()
+            .add_event::<MenuSelected>()
+            .add_systems(OnEnter(GameState::Menu), spawn_menu)
+            .add_systems(OnEnter(GameState::Options), spawn_options_menu)
+            .add_systems(OnEnter(GameState::GameOver), spawn_game_over_screen)
+            .add_systems(
+                Update,
+                // a replay pauses and resumes by itself
+                (
+                    focus_menu_buttons,
+                    // a fire still held from the game doesn't skip past the game over
+                    // tally before it could be read
+                    select_menu_buttons.run_if(typed_out),
+                    handle_menu_buttons,
+                )
+                    .chain()
+                    .in_set(GameSet::Input)
+                    .run_if(any_with_component::<Menu>)
+                    .run_if(not(resource_exists::<ReplayPlayer>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    highlight_focused_button.run_if(any_with_component::<Menu>),
+                    update_option_labels.run_if(in_state(GameState::Options)),
+                )
+                    .in_set(GameSet::Presentation),
+            );
+    }
+}
+
+// pauses the game under a menu while playing, except when playing back a replay
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PauseState::Paused), (pause_time, spawn_pause_menu))
+            .add_systems(OnExit(PauseState::Paused), resume_time)
+            .add_systems(
+                Update,
+                toggle_pause
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<ReplayPlayer>))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+// the cannon and the input controlling it
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ControllerEvent>()
+            .add_event::<Fired>()
+            .add_systems(OnEnter(GameState::LevelComplete), discard_fired)
+            .add_systems(OnExit(GameState::LevelComplete), discard_fired)
+            .add_systems(OnExit(GameState::Menu), setup_player)
+            .add_systems(OnExit(GameState::GameOver), setup_player)
+            .add_systems(OnEnter(GameState::Playing), spawn_cannon)
+            .add_systems(
+                Update,
+                (
+                    move_cannon.in_set(GameSet::Movement),
+                    lose_lifes.in_set(GameSet::Scoring),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            // fire also skips the transition between levels
+            .add_systems(
+                Update,
+                player_input
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<ReplayPlayer>))
+                    .run_if(
+                        in_state(GameState::Playing).or_else(in_state(GameState::LevelComplete)),
+                    )
+                    .run_if(not(in_state(PauseState::Paused))),
+            );
+    }
+}
+
+// the invaders marching down the screen
+pub struct FormationPlugin;
+
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EnemyAdvancement>()
+            .add_systems(OnEnter(GameState::Playing), spawn_enemies)
+            .add_systems(
+                FixedUpdate,
+                (
+                    move_enemies.in_set(GameSet::Movement),
+                    increase_difficulty.in_set(GameSet::Rules),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (remove_shot_invaders, speed_up_march_on_kills)
+                    .in_set(GameSet::Scoring)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+// finds what ran into what, for the other plugins to decide what happens
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Collision>()
+            .init_resource::<PixelMasks>()
+            // the sprites are only known once they are loaded
+            .add_systems(OnExit(GameState::Loading), build_pixel_masks)
+            .add_systems(
+                Update,
+                (detect_collisions, update_sweeps)
+                    .chain()
+                    .in_set(GameSet::Collision)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+// the laser of the cannon, the bombs of the invaders and what they hit
+pub struct ProjectilesPlugin;
+
+impl Plugin for ProjectilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HitEvent>()
+            .add_event::<PlayerHitEvent>()
+            .add_event::<ShotFired>()
+            .add_systems(
+                Update,
+                (
+                    // the laser leaves from where the cannon moved to
+                    fire_laser.after(move_cannon).in_set(GameSet::Movement),
+                    (move_laser_beam, move_bomb).in_set(GameSet::Movement),
+                    (resolve_laser_hits, resolve_bomb_hits)
+                        .chain()
+                        .after(detect_collisions)
+                        .in_set(GameSet::Collision),
+                    (score_hits, count_shots).in_set(GameSet::Scoring),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                // the bombs drop from where the invaders moved to
+                (
+                    tick_bomb_timer,
+                    drop_bomb.run_if(bomb_timer_finished),
+                    report_dropped_bombs,
+                )
+                    .chain()
+                    .after(move_enemies)
+                    .in_set(GameSet::Movement)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+pub struct UfoPlugin;
+
+impl Plugin for UfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UfoSpawned>()
+            .add_event::<UfoEscaped>()
+            // with the difficulty of the level
+            .add_systems(OnEnter(GameState::Playing), setup_ufo_timer.after(reset))
+            .add_systems(
+                Update,
+                move_ufo
+                    .in_set(GameSet::Movement)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            // after the bombs, so both draw from the random numbers in the same order
+            .add_systems(
+                FixedUpdate,
+                (
+                    tick_ufo_spawn_timer,
+                    spawn_ufo.run_if(ufo_spawn_timer_finished),
+                )
+                    .chain()
+                    .after(drop_bomb)
+                    .in_set(GameSet::Movement)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+// the score and the lifes left
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            (spawn_lifes_ui, spawn_score_ui),
+        )
+        .add_systems(
+            Update,
+            (update_score_ui, update_lifes_ui)
+                .in_set(GameSet::Presentation)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+// nudges the difficulty to how well the game goes, when turned on in the settings
+pub struct AdaptiveDifficultyPlugin;
+
+impl Plugin for AdaptiveDifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AdaptiveDifficulty>().add_systems(
+            Update,
+            (
+                start_adaptive_level,
+                adapt_to_deaths.in_set(GameSet::Scoring),
+                adapt_to_cleared_level
+                    .after(check_level_complete)
+                    .in_set(GameSet::Rules),
+            )
+                .run_if(adaptive_difficulty_enabled),
+        );
+    }
+}
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>()
+            .add_systems(OnEnter(GameState::Playing), spawn_debug_overlay)
+            .add_systems(
+                Update,
+                (
+                    toggle_debug_overlay.in_set(GameSet::Input),
+                    update_debug_overlay
+                        .in_set(GameSet::Presentation)
+                        .run_if(in_state(GameState::Playing)),
+                ),
+            );
+    }
+}
+
+// the marching notes of the invaders and the sound of hitting them
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentNoteIndex>()
+            .add_systems(
+                Update,
+                play_enemy_hit_sound
+                    .in_set(GameSet::Presentation)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                play_invader_sound
+                    .in_set(GameSet::Presentation)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+// records and plays back replays, when set up from the command line
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        // replays cover everything from the start of the game up to game over
+        app.add_systems(
+            Update,
+            (
+                record_replay_frame
+                    .after(player_input)
+                    .run_if(resource_exists::<ReplayRecorder>),
+                (
+                    end_replay.run_if(resource_exists::<ReplayPlayer>.and_then(replay_finished)),
+                    play_replay_frame.run_if(resource_exists::<ReplayPlayer>),
+                )
+                    .chain(),
+            )
+                .in_set(GameSet::Input)
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::LevelComplete))),
+        )
+        .add_systems(OnEnter(GameState::GameOver), finish_replay)
+        // quitting to the menu ends the recording too, so another game isn't added to it
+        .add_systems(
+            OnEnter(GameState::Menu),
+            finish_replay.run_if(resource_exists::<ReplayRecorder>),
+        )
+        .add_systems(Last, save_replay_on_exit);
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{app::AppExit, time::TimeUpdateStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, PauseState};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ReplayFrame {
+    // the real time the frame took, in nanoseconds
+    pub duration: u64,
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+    // whether the game was paused, which can change from one frame to the next
+    #[serde(default)]
+    pub paused: bool,
+}
+
+// the input of every frame from the start of the game, which together with the seed
+// is enough to play the game again exactly as it was played
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Replay {
+    pub settings: GameSettings,
+    pub frames: Vec<ReplayFrame>,
+    // the score at game over or when quitting to the menu, to check the replay plays
+    // back the same, which games closed in the middle of them don't have
+    #[serde(default)]
+    pub score: Option<i32>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let replay = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let replay: Self = ron::from_str(&replay).map_err(|error| error.to_string())?;
+        replay.settings.check()?;
+        Ok(replay)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let replay = ron::to_string(self).map_err(|error| error.to_string())?;
+        fs::write(path, replay).map_err(|error| error.to_string())
+    }
+
+    pub fn frame_duration(&self, frame: usize) -> Duration {
+        self.frames
+            .get(frame)
+            .map_or(Duration::from_secs_f64(1. / 60.), |frame| {
+                Duration::from_nanos(frame.duration)
+            })
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    pub fn new(path: PathBuf, settings: GameSettings) -> Self {
+        Self {
+            path,
+            replay: Replay {
+                settings,
+                frames: Vec::new(),
+                score: None,
+            },
+        }
+    }
+
+    // records the game played with the settings, which starts right away as the menu
+    // isn't recorded
+    pub fn install(app: &mut App, path: PathBuf, settings: GameSettings) {
+        AfterLoading::skip_menu(app);
+        app.insert_resource(ReplayRecorder::new(path, settings.clone()))
+            .insert_resource(settings);
+    }
+
+    fn save(&self) {
+        match self.replay.save(&self.path) {
+            Ok(()) => info!(
+                "Recorded {} frames to {}",
+                self.replay.frames.len(),
+                self.path.display()
+            ),
+            Err(error) => error!("Failed to save replay {}: {}", self.path.display(), error),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    frame: usize,
+    // exit once the replay is over instead of handing over to the keyboard
+    exit_when_done: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay, exit_when_done: bool) -> Self {
+        Self {
+            replay,
+            frame: 0,
+            exit_when_done,
+        }
+    }
+
+    // plays the replay back with the settings it was recorded with
+    pub fn install(app: &mut App, replay: Replay, exit_when_done: bool) {
+        AfterLoading::skip_menu(app);
+        // the frames have to take exactly as long as they did while recording
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(replay.frame_duration(0)))
+            .insert_resource(replay.settings.clone())
+            .insert_resource(ReplayPlayer::new(replay, exit_when_done));
+    }
+}
+
+pub fn record_replay_frame(
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time<Real>>,
+    pause_state: Option<Res<State<PauseState>>>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    // player_input doesn't read the keys while paused
+    let paused = pause_state.is_some_and(|state| *state.get() == PauseState::Paused);
+
+    // the same keys player_input reads
+    recorder.replay.frames.push(ReplayFrame {
+        duration: time.delta().as_nanos() as u64,
+        left: !paused && keys.pressed(KeyCode::ArrowLeft),
+        right: !paused && keys.pressed(KeyCode::ArrowRight),
+        fire: !paused && keys.just_pressed(KeyCode::Space),
+        paused,
+    });
+}
+
+pub fn replay_finished(replay_player: Res<ReplayPlayer>) -> bool {
+    replay_player.frame >= replay_player.replay.frames.len()
+}
+
+pub fn end_replay(
+    mut commands: Commands,
+    replay_player: Res<ReplayPlayer>,
+    player: Res<Player>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+    mut exit_event_writer: EventWriter<AppExit>,
+) {
+    stop_replay(
+        &mut commands,
+        &replay_player,
+        &player,
+        &mut time_update_strategy,
+        &mut exit_event_writer,
+    );
+}
+
+pub fn play_replay_frame(
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut controller_event_writer: EventWriter<ControllerEvent>,
+    mut fired_event_writer: EventWriter<Fired>,
+) {
+    let Some(frame) = replay_player
+        .replay
+        .frames
+        .get(replay_player.frame)
+        .copied()
+    else {
+        return;
+    };
+
+    if frame.left {
+        controller_event_writer.send(ControllerEvent {
+            direction: ControllerDirection::Left,
+        });
+    }
+    if frame.right {
+        controller_event_writer.send(ControllerEvent {
+            direction: ControllerDirection::Right,
+        });
+    }
+    if frame.fire {
+        fired_event_writer.send(Fired);
+    }
+
+    // the duration of a frame is set before it starts, and so is pausing, which takes
+    // effect in the next frame just as it did while recording
+    replay_player.frame += 1;
+    *time_update_strategy = TimeUpdateStrategy::ManualDuration(
+        replay_player.replay.frame_duration(replay_player.frame),
+    );
+
+    if let Some(next_frame) = replay_player.replay.frames.get(replay_player.frame) {
+        if next_frame.paused != frame.paused {
+            next_pause_state.set(match next_frame.paused {
+                true => PauseState::Paused,
+                false => PauseState::Running,
+            });
+        }
+    }
+}
+
+pub fn finish_replay(
+    mut commands: Commands,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    player: Res<Player>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+    mut exit_event_writer: EventWriter<AppExit>,
+) {
+    if let Some(mut recorder) = recorder {
+        recorder.replay.score = Some(player.score);
+        recorder.save();
+        commands.remove_resource::<ReplayRecorder>();
+    }
+
+    if let Some(replay_player) = replay_player {
+        stop_replay(
+            &mut commands,
+            &replay_player,
+            &player,
+            &mut time_update_strategy,
+            &mut exit_event_writer,
+        );
+    }
+}
+
+// keeps what was recorded so far when the game is closed in the middle of it
+pub fn save_replay_on_exit(
+    mut exit_event_reader: EventReader<AppExit>,
+    recorder: Option<Res<ReplayRecorder>>,
+) {
+    if exit_event_reader.is_empty() {
+        return;
+    }
+    exit_event_reader.clear();
+
+    if let Some(recorder) = recorder {
+        recorder.save();
+    }
+}
+
+fn stop_replay(
+    commands: &mut Commands,
+    replay_player: &ReplayPlayer,
+    player: &Player,
+    time_update_strategy: &mut TimeUpdateStrategy,
+    exit_event_writer: &mut EventWriter<AppExit>,
+) {
+    info!(
+        "Replay finished after {} frames with a score of {}",
+        replay_player.frame, player.score
+    );
+
+    if let Some(score) = replay_player.replay.score {
+        if score != player.score {
+            warn!(
+                "Replay played back differently than recorded, which ended with a score of {}",
+                score
+            );
+        }
+    }
+
+    if replay_player.exit_when_done {
+        exit_event_writer.send(AppExit::Success);
+    } else {
+        commands.remove_resource::<ReplayPlayer>();
+        *time_update_strategy = TimeUpdateStrategy::Automatic;
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::{
+    render::{
+        camera::{ScalingMode, Viewport},
+        view::RenderLayers,
+    },
+    window::{PrimaryWindow, WindowMode},
+};
+
+use crate::prelude::*;
+
+// the letterbox camera only renders this otherwise unused layer, so it just clears the bars
+const LETTERBOX_LAYER: usize = 1;
+
+#[derive(Component)]
+pub struct GameCamera;
+
+pub fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: -1,
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(LETTERBOX_LAYER),
+    ));
+
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: SCREEN_WIDTH,
+        height: SCREEN_HEIGHT,
+    };
+
+    commands.spawn((camera, GameCamera));
+}
+
+// scales the native resolution by the largest integer factor that fits the window and
+// centers the result, leaving the rest of the window to the letterbox camera
+pub fn fit_camera_to_window(
+    window_query: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut camera_query: Query<&mut Camera, With<GameCamera>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    if window_size.x == 0 || window_size.y == 0 {
+        return;
+    }
+
+    let native_size = Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT).as_uvec2();
+    let scale = (window_size / native_size).min_element().max(1);
+    let viewport_size = (native_size * scale).min(window_size);
+
+    let mut camera = camera_query.single_mut();
+    camera.viewport = Some(Viewport {
+        physical_position: (window_size - viewport_size) / 2,
+        physical_size: viewport_size,
+        ..default()
+    });
+
+    // lay out the UI in native pixels as well
+    ui_scale.0 = scale as f32 / window.scale_factor();
+}
+
+pub fn toggle_fullscreen(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !keys.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    let mut window = window_query.single_mut();
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
+}
@@ -1,111 +1,114 @@
-use crate::prelude::*;
+use crate::{prelude::*, GameState, PauseState};
 
-pub fn spawn_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+pub fn setup_player(mut commands: Commands, settings: Res<GameSettings>) {
+    info!("Starting game with {:?}", *settings);
+    commands.insert_resource(Player::new(settings.lifes));
+    commands.insert_resource(AdaptiveDifficulty::default());
+    commands.insert_resource(Level(settings.level as u32));
 }
 
-pub fn setup_player(mut commands: Commands) {
-    commands.insert_resource(Player::new());
-    commands.insert_resource(Level(1.));
-}
+pub fn reset(
+    mut commands: Commands,
+    level: Res<Level>,
+    settings: Res<GameSettings>,
+    adaptive_difficulty: Res<AdaptiveDifficulty>,
+    mut player: ResMut<Player>,
+    mut difficulty: ResMut<Difficulty>,
+    mut level_started_event_writer: EventWriter<LevelStarted>,
+) {
+    player.start_level();
+
+    *difficulty = settings.difficulty.into();
+    if settings.adaptive {
+        adaptive_difficulty.apply(&mut difficulty);
+    }
 
-pub fn reset(mut commands: Commands) {
-    let difficulty = Difficulty::default();
-    commands.insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(
-        (difficulty.0 * 10) as u64,
+    commands.insert_resource(EnemyMovement::new(&difficulty));
+    commands.insert_resource(BombTimer(Timer::new(
+        difficulty.bomb_interval,
+        TimerMode::Repeating,
     )));
-    commands.insert_resource(difficulty);
 
-    commands.insert_resource(EnemyMovement::new());
+    level_started_event_writer.send(LevelStarted { level: level.0 });
 }
 
-pub fn spawn_cannon(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    let texture: Handle<Image> = asset_server.load("cannon.png");
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 2, 1, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-
-    commands.spawn(CannonBundle::new(texture, texture_atlas_layout));
+pub fn spawn_cannon(mut commands: Commands, atlas: Res<SpriteAtlas>) {
+    commands.spawn((CannonBundle::new(&atlas), StateScoped(GameState::Playing)));
 }
 
 pub fn spawn_lifes_ui(mut commands: Commands) {
     commands.spawn((
-        TextBundle::from_sections([TextSection::from_style(TextStyle {
-            font_size: TEXT_SIZE,
-            color: TEXT_COLOR,
-            ..default()
-        })])
-        .with_style(Style {
+        BitmapTextBundle::new("", TEXT_SIZE).with_style(Style {
             position_type: PositionType::Absolute,
             top: Val::Px(SCREEN_HEIGHT - BOTTOM_MENU_HEIGHT),
             left: Val::Px(0.),
             ..default()
         }),
         LifesUI,
+        StateScoped(GameState::Playing),
     ));
 }
 
 pub fn spawn_score_ui(mut commands: Commands) {
     commands.spawn((
-        TextBundle::from_sections([TextSection::from_style(TextStyle {
-            font_size: TEXT_SIZE,
-            color: TEXT_COLOR,
-            ..default()
-        })])
-        .with_style(Style {
+        BitmapTextBundle::new("", TEXT_SIZE).with_style(Style {
             position_type: PositionType::Absolute,
             top: Val::Px(0.),
             left: Val::Px(0.),
             ..default()
         }),
         ScoreUI,
+        StateScoped(GameState::Playing),
     ));
 }
 
 pub fn spawn_ufo(
     mut commands: Commands,
-    time: Res<Time>,
-    mut spawn_ufo_timer_query: Query<&mut UfoSpawnTimer>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    ufo_query: Query<(), With<Ufo>>,
+    atlas: Res<SpriteAtlas>,
     enemy_movement: Res<EnemyMovement>,
+    mut rng: ResMut<GameRng>,
+    mut ufo_spawned_event_writer: EventWriter<UfoSpawned>,
 ) {
-    for mut timer in &mut spawn_ufo_timer_query {
-        if !timer.tick(time.delta()).just_finished() {
-            return;
-        }
+    // the next one only comes once the last one is gone
+    if !ufo_query.is_empty() {
+        return;
     }
-    let texture: Handle<Image> = asset_server.load("ufo.png");
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 1, 2, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
 
     let start_x: f32 = match enemy_movement.direction > 0. {
         true => LEFT_WALL + SPRITE_SIZE / 2.,
         false => RIGHT_WALL - SPRITE_SIZE / 2.,
     };
 
-    commands.spawn(UfoBundle::new(
-        start_x,
-        TOP_WALL - SPRITE_SIZE / 2.,
-        texture.clone(),
-        texture_atlas_layout.clone(),
-        enemy_movement.direction,
+    commands.spawn((
+        UfoBundle::new(
+            start_x,
+            TOP_WALL - SPRITE_SIZE / 2.,
+            &atlas,
+            enemy_movement.direction,
+            &mut rng,
+        ),
+        StateScoped(GameState::Playing),
     ));
+    ufo_spawned_event_writer.send(UfoSpawned {
+        position: Vec2::new(start_x, TOP_WALL - SPRITE_SIZE / 2.),
+        direction: enemy_movement.direction,
+    });
 }
 
 pub fn spawn_enemies(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    atlas: Res<SpriteAtlas>,
     level: Res<Level>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    starting_heights: Res<StartingHeights>,
 ) {
-    let mut y = TOP_WALL - SPRITE_SIZE * level.0;
+    let mut formation = Formation::new(Vec2::new(
+        SPRITE_SIZE - SCREEN_WIDTH / 2.,
+        TOP_WALL - SPRITE_SIZE - starting_heights.drop(level.0),
+    ));
 
     // spawns a row of enemies
-    for row in 0..5 {
+    for row in 0..FORMATION_ROWS as u8 {
         let enemy = match row {
             0 => Enemy::squid(),
             1 => Enemy::crab(),
@@ -113,71 +116,63 @@ pub fn spawn_enemies(
             _ => Enemy::octopus(),
         };
 
-        let texture: Handle<Image> = asset_server.load(enemy.sprite_file_name.clone());
-        let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 2, 1, None, None);
-        let texture_atlas_layout = texture_atlas_layouts.add(layout);
-
-        for col in 0..11 {
-            let offset = (SPRITE_SIZE + 1.) * col as f32 - SCREEN_WIDTH / 2. + SPRITE_SIZE;
-            let mut enemy_bundle = EnemyBundle::new(
-                enemy.clone(),
-                offset,
-                y,
-                texture.clone(),
-                texture_atlas_layout.clone(),
-            );
-            enemy_bundle.position = EnemyPosition { x: col, y: row };
-
-            commands.spawn(enemy_bundle);
-        }
+        for col in 0..FORMATION_COLUMNS as u8 {
+            let position = EnemyPosition { x: col, y: row };
+            let cell_position = formation.cell_position(&position);
+            let mut enemy_bundle =
+                EnemyBundle::new(enemy.clone(), cell_position.x, cell_position.y, &atlas);
+            enemy_bundle.position = position;
 
-        // switch to next line
-        y -= SPRITE_SIZE + 1.;
+            let entity = commands
+                .spawn((enemy_bundle, StateScoped(GameState::Playing)))
+                .id();
+            formation.insert(&position, entity);
+        }
     }
-}
 
-pub fn despawn_game(
-    mut commands: Commands,
-    cannon_query: Query<Entity, With<Cannon>>,
-    enemies_query: Query<Entity, With<Enemy>>,
-    score_ui_query: Query<Entity, With<ScoreUI>>,
-    lifes_ui_query: Query<Entity, With<LifesUI>>,
-) {
-    cannon_query
-        .iter()
-        .for_each(|entity| commands.entity(entity).despawn_recursive());
-    enemies_query
-        .iter()
-        .for_each(|entity| commands.entity(entity).despawn_recursive());
-    score_ui_query
-        .iter()
-        .for_each(|entity| commands.entity(entity).despawn_recursive());
-    lifes_ui_query
-        .iter()
-        .for_each(|entity| commands.entity(entity).despawn_recursive());
+    commands.insert_resource(formation);
 }
 
-const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
-
 #[derive(Component, Clone, Copy, PartialEq, Eq)]
 pub enum ButtonAction {
     StartGame,
+    Options,
+    CycleTheme,
+    ToggleOverlay,
+    CycleDifficulty,
+    ToggleAdaptive,
+    Resume,
+    Back,
 }
 
-pub fn spawn_menu(mut commands: Commands) {
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum OptionLabel {
+    Theme,
+    Overlay,
+    Difficulty,
+    Adaptive,
+}
+
+// the menu is removed again when leaving the state it was spawned in, and the lines
+// above its buttons are typed out one after the other
+fn spawn_menu_buttons<S: States>(
+    commands: &mut Commands,
+    state: S,
+    lines: Vec<String>,
+    buttons: Vec<(ButtonAction, BitmapTextBundle)>,
+) {
     let button_style = Style {
-        width: Val::Px(300.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
+        width: Val::Px(160.0),
+        height: Val::Px(24.0),
+        margin: UiRect::all(Val::Px(8.0)),
+        // only drawn around the focused button
+        border: UiRect::all(Val::Px(1.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
     };
-    let button_text_style = TextStyle {
-        font_size: 40.0,
-        color: TEXT_COLOR,
-        ..default()
-    };
+
+    let mut menu_buttons = Vec::new();
 
     commands
         .spawn((
@@ -191,7 +186,7 @@ pub fn spawn_menu(mut commands: Commands) {
                 },
                 ..default()
             },
-            Menu,
+            StateScoped(state),
         ))
         .with_children(|parent| {
             parent
@@ -206,34 +201,154 @@ pub fn spawn_menu(mut commands: Commands) {
                     ..default()
                 })
                 .with_children(|parent| {
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
+                    let mut delay = Duration::ZERO;
+                    for line in lines {
+                        let typewriter = Typewriter::new(line, delay);
+                        delay += typewriter.duration();
+
+                        parent.spawn((
+                            BitmapTextBundle::new("", TEXT_SIZE).with_style(Style {
+                                margin: UiRect::bottom(Val::Px(8.)),
                                 ..default()
-                            },
-                            ButtonAction::StartGame,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Start Game",
-                                button_text_style.clone(),
-                            ));
-                        });
+                            }),
+                            typewriter,
+                        ));
+                    }
+
+                    for (action, label) in buttons {
+                        let button = parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    ..default()
+                                },
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                let mut label = parent.spawn(label);
+
+                                match action {
+                                    ButtonAction::CycleTheme => label.insert(OptionLabel::Theme),
+                                    ButtonAction::ToggleOverlay => {
+                                        label.insert(OptionLabel::Overlay)
+                                    }
+                                    ButtonAction::CycleDifficulty => {
+                                        label.insert(OptionLabel::Difficulty)
+                                    }
+                                    ButtonAction::ToggleAdaptive => {
+                                        label.insert(OptionLabel::Adaptive)
+                                    }
+                                    _ => &mut label,
+                                };
+                            })
+                            .id();
+                        menu_buttons.push(button);
+                    }
                 });
+        })
+        .insert(Menu {
+            buttons: menu_buttons,
+            focus: 0,
         });
 }
 
-pub fn despawn_menu(
+pub fn spawn_menu(mut commands: Commands) {
+    spawn_menu_buttons(
+        &mut commands,
+        GameState::Menu,
+        Vec::new(),
+        vec![
+            (
+                ButtonAction::StartGame,
+                BitmapTextBundle::new("Start Game", TEXT_SIZE),
+            ),
+            (
+                ButtonAction::Options,
+                BitmapTextBundle::new("Options", TEXT_SIZE),
+            ),
+        ],
+    );
+}
+
+pub fn spawn_options_menu(mut commands: Commands) {
+    // the option labels are filled in by update_option_labels
+    spawn_menu_buttons(
+        &mut commands,
+        GameState::Options,
+        Vec::new(),
+        vec![
+            (
+                ButtonAction::CycleTheme,
+                BitmapTextBundle::new("", TEXT_SIZE),
+            ),
+            (
+                ButtonAction::ToggleOverlay,
+                BitmapTextBundle::new("", TEXT_SIZE),
+            ),
+            (
+                ButtonAction::CycleDifficulty,
+                BitmapTextBundle::new("", TEXT_SIZE),
+            ),
+            (
+                ButtonAction::ToggleAdaptive,
+                BitmapTextBundle::new("", TEXT_SIZE),
+            ),
+            (ButtonAction::Back, BitmapTextBundle::new("Back", TEXT_SIZE)),
+        ],
+    );
+}
+
+// the final tally of the game, which also puts its score into the high scores
+pub fn spawn_game_over_screen(
     mut commands: Commands,
-    menu_query: Query<Entity, With<Menu>>,
-    game_over_sign_query: Query<Entity, With<GameOverSign>>,
+    player: Res<Player>,
+    level: Res<Level>,
+    mut high_scores: ResMut<HighScores>,
 ) {
-    if let Ok(menu) = menu_query.get_single() {
-        commands.entity(menu).despawn_recursive();
-    }
+    let rank = match high_scores.insert(player.score) {
+        Some(rank) => format!("High score rank {}", rank),
+        None => "No high score".into(),
+    };
 
-    if let Ok(game_over_sign) = game_over_sign_query.get_single() {
-        commands.entity(game_over_sign).despawn_recursive();
-    }
+    spawn_menu_buttons(
+        &mut commands,
+        GameState::GameOver,
+        vec![
+            "GAME OVER".into(),
+            format!("Score {:04}", player.score),
+            format!("Round {}", level.0),
+            format!("Accuracy {}%", (player.accuracy() * 100.).round()),
+            format!("Invaders {}", player.invaders_destroyed),
+            format!("UFOs {}", player.ufos_hit),
+            rank,
+        ],
+        vec![
+            (
+                ButtonAction::StartGame,
+                BitmapTextBundle::new("Retry", TEXT_SIZE),
+            ),
+            (
+                ButtonAction::Back,
+                BitmapTextBundle::new("Main Menu", TEXT_SIZE),
+            ),
+        ],
+    );
+}
+
+pub fn spawn_pause_menu(mut commands: Commands) {
+    spawn_menu_buttons(
+        &mut commands,
+        PauseState::Paused,
+        vec!["PAUSED".into()],
+        vec![
+            (
+                ButtonAction::Resume,
+                BitmapTextBundle::new("Resume", TEXT_SIZE),
+            ),
+            (
+                ButtonAction::Back,
+                BitmapTextBundle::new("Main Menu", TEXT_SIZE),
+            ),
+        ],
+    );
 }
@@ -1,12 +1,11 @@
-use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+use rand::Rng;
 
-use crate::{prelude::*, GameState};
+use crate::{prelude::*, GameState, PauseState};
 
-const LASER_SPEED: f32 = 8.;
-const CANNON_SPEED: f32 = 3.;
-const BOMB_SPEED: f32 = 1.;
-const UFO_SPEED: f32 = 1.;
-const DIFFICULTY_DELTA: i32 = 7;
+// in units per second, so everything moves as fast whatever the frame rate
+const LASER_SPEED: f32 = 240.;
+const CANNON_SPEED: f32 = 90.;
+const UFO_SPEED: f32 = 30.;
 
 #[derive(Clone, Copy, Debug)]
 pub enum ControllerDirection {
@@ -16,12 +15,16 @@ pub enum ControllerDirection {
 
 #[derive(Event, Clone, Copy, Debug)]
 pub struct ControllerEvent {
-    direction: ControllerDirection,
+    pub direction: ControllerDirection,
 }
 
 #[derive(Event, Debug)]
 pub struct Fired;
 
+// a menu button was pressed, with the mouse, the keyboard or a gamepad
+#[derive(Event, Clone, Copy)]
+pub struct MenuSelected(pub ButtonAction);
+
 pub fn player_input(
     keys: Res<ButtonInput<KeyCode>>,
     mut controller_event_writer: EventWriter<ControllerEvent>,
@@ -44,10 +47,14 @@ pub fn player_input(
 }
 
 pub fn move_cannon(
+    time: Res<Time>,
     mut cannon_transform_query: Query<&mut Transform, With<Cannon>>,
     mut controller_event_reader: EventReader<ControllerEvent>,
 ) {
-    let mut cannon_transform = cannon_transform_query.single_mut();
+    // the cannon is gone for the rest of the frame once the game is over
+    let Ok(mut cannon_transform) = cannon_transform_query.get_single_mut() else {
+        return;
+    };
 
     let mut direction: f32 = 0.;
 
@@ -58,54 +65,79 @@ pub fn move_cannon(
         }
     }
 
-    let new_cannon_position = cannon_transform.translation.x + direction * CANNON_SPEED;
+    let new_cannon_position =
+        cannon_transform.translation.x + direction * CANNON_SPEED * time.delta_seconds();
 
     cannon_transform.translation.x =
         new_cannon_position.clamp(LEFT_WALL + SPRITE_SIZE / 2., RIGHT_WALL - SPRITE_SIZE / 2.);
 }
 
 pub fn move_enemies(
-    mut enemy_query: Query<&mut Transform, With<Enemy>>,
+    time: Res<Time>,
+    mut enemy_query: Query<(&mut Transform, &EnemyPosition), With<Enemy>>,
+    mut formation: ResMut<Formation>,
     mut enemy_movement: ResMut<EnemyMovement>,
     mut enemy_advancement_event_writer: EventWriter<EnemyAdvancement>,
 ) {
+    if !enemy_movement.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
     if enemy_movement.advance {
-        enemy_query.iter_mut().for_each(|mut transform| {
-            transform.translation.y -= SPRITE_SIZE;
-        });
+        formation.origin.y -= SPRITE_SIZE;
         enemy_movement.advance = false;
     } else {
-        let mut advance = false;
-        for mut transform in enemy_query.iter_mut() {
-            let new_x = transform.translation.x + enemy_movement.speed * enemy_movement.direction;
+        formation.origin.x += enemy_movement.speed * enemy_movement.direction;
 
-            if new_x + SPRITE_SIZE > RIGHT_WALL || new_x - SPRITE_SIZE < LEFT_WALL {
-                advance = true;
-            }
-
-            transform.translation.x = new_x;
-        }
+        let advance = formation.bounds().is_some_and(|bounds| {
+            bounds.max.x + SPRITE_SIZE / 2. > RIGHT_WALL
+                || bounds.min.x - SPRITE_SIZE / 2. < LEFT_WALL
+        });
 
         if advance {
-            enemy_advancement_event_writer.send_default();
             enemy_movement.reverse_direction();
+            enemy_advancement_event_writer.send(EnemyAdvancement {
+                direction: enemy_movement.direction,
+            });
             enemy_movement.advance = true;
         }
     }
+
+    for (mut transform, position) in enemy_query.iter_mut() {
+        let cell_position = formation.cell_position(position);
+        transform.translation.x = cell_position.x;
+        transform.translation.y = cell_position.y;
+    }
 }
 
 pub fn increase_difficulty(
-    mut commands: Commands,
     mut difficulty: ResMut<Difficulty>,
+    mut enemy_movement: ResMut<EnemyMovement>,
     mut enemy_advancement_event_reader: EventReader<EnemyAdvancement>,
 ) {
     if !enemy_advancement_event_reader.is_empty() {
         enemy_advancement_event_reader.clear();
-        difficulty.0 -= DIFFICULTY_DELTA;
+        let speedup = difficulty.march_speedup;
+        difficulty.speed_up_march(speedup);
+
+        let march_interval = difficulty.march_interval;
+        enemy_movement.timer.set_duration(march_interval);
+    }
+}
+
+pub fn speed_up_march_on_kills(
+    mut difficulty: ResMut<Difficulty>,
+    mut enemy_movement: ResMut<EnemyMovement>,
+    mut hit_event_reader: EventReader<HitEvent>,
+) {
+    for hit in hit_event_reader.read() {
+        if let HitKind::Invader(_) = hit.kind {
+            let speedup = difficulty.kill_speedup;
+            difficulty.speed_up_march(speedup);
 
-        commands.insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(
-            (difficulty.0 * 10) as u64,
-        )));
+            let march_interval = difficulty.march_interval;
+            enemy_movement.timer.set_duration(march_interval);
+        }
     }
 }
 
@@ -114,35 +146,49 @@ pub fn fire_laser(
     mut fired_event_reader: EventReader<Fired>,
     cannon_query: Query<&Transform, With<Cannon>>,
     laser_beam_query: Query<&LaserBeam>,
-    sound: Res<FireLaserSound>,
+    game_assets: Res<GameAssets>,
+    atlas: Res<SpriteAtlas>,
+    mut shot_fired_event_writer: EventWriter<ShotFired>,
 ) {
     // only one laser beam at the time
     if !laser_beam_query.is_empty() {
         return;
     }
 
-    let cannon_transform = cannon_query.single();
+    let Ok(cannon_transform) = cannon_query.get_single() else {
+        return;
+    };
 
     if !fired_event_reader.is_empty() {
         fired_event_reader.clear();
         commands.spawn(AudioBundle {
-            source: sound.clone(),
+            source: game_assets.fire_laser_sound.clone(),
             settings: PlaybackSettings::DESPAWN,
         });
 
-        commands.spawn(LaserBeamBundle::new(
-            cannon_transform.translation.x,
-            cannon_transform.translation.y,
+        commands.spawn((
+            LaserBeamBundle::new(
+                cannon_transform.translation.x,
+                cannon_transform.translation.y,
+                &atlas,
+            ),
+            StateScoped(GameState::Playing),
         ));
+        shot_fired_event_writer.send(ShotFired {
+            shooter: Shooter::Cannon,
+            projectile: Projectile::Laser,
+            position: cannon_transform.translation.truncate(),
+        });
     }
 }
 
 pub fn move_laser_beam(
     mut commands: Commands,
+    time: Res<Time>,
     mut laser_beam_query: Query<(Entity, &mut Transform), With<LaserBeam>>,
 ) {
     for (entity, mut laser_beam_transform) in laser_beam_query.iter_mut() {
-        laser_beam_transform.translation.y += 1. * LASER_SPEED;
+        laser_beam_transform.translation.y += LASER_SPEED * time.delta_seconds();
 
         if laser_beam_transform.translation.y >= TOP_WALL {
             commands.entity(entity).despawn_recursive();
@@ -150,40 +196,49 @@ pub fn move_laser_beam(
     }
 }
 
-pub fn detect_laser_hit(
+pub fn resolve_laser_hits(
     mut commands: Commands,
-    mut player: ResMut<Player>,
-    laser_beam_query: Query<(Entity, &Transform, &Size), With<LaserBeam>>,
-    hitable_query: Query<(Entity, &Transform, &Size, Option<&Hitpoints>), With<Hitable>>,
+    mut collision_event_reader: EventReader<Collision>,
+    hitable_query: Query<(&Hitable, Option<&Hitpoints>)>,
+    piercing_query: Query<(), With<Piercing>>,
     mut hit_event_writer: EventWriter<HitEvent>,
 ) {
-    if let Ok((laser_beam_entity, laser_beam_transform, laser_beam_size)) =
-        laser_beam_query.get_single()
-    {
-        let laser_beam_bounding_box: Aabb2d = Aabb2d::new(
-            laser_beam_transform.translation.truncate(),
-            Vec2::new(laser_beam_size.width, laser_beam_size.height) / 2.,
-        );
+    // a laser only hits the first thing in its way, unless it pierces through all of them
+    let mut hits: Vec<Collision> = Vec::new();
 
-        for (entity, transform, hitable_size, maybe_hitpoints) in hitable_query.iter() {
-            let mut points: i32 = 0;
+    for collision in collision_event_reader.read() {
+        if collision.layer != CollisionLayer::PlayerShot {
+            continue;
+        }
 
-            if let Some(hitpoints) = maybe_hitpoints {
-                points = hitpoints.0;
-            }
+        if piercing_query.contains(collision.entity) {
+            hits.push(*collision);
+            continue;
+        }
 
-            let bounding_box: Aabb2d = Aabb2d::new(
-                transform.translation.truncate(),
-                Vec2::new(hitable_size.width, hitable_size.height) / 2.,
-            );
+        match hits.iter_mut().find(|hit| hit.entity == collision.entity) {
+            Some(hit) if collision.distance < hit.distance => *hit = *collision,
+            Some(_) => {}
+            None => hits.push(*collision),
+        }
+    }
 
-            if bounding_box.intersects(&laser_beam_bounding_box) {
-                hit_event_writer.send_default();
-                player.add_to_score(points);
+    for hit in hits {
+        // bunkers stop the laser without being scored
+        if let Ok((hitable, maybe_hitpoints)) = hitable_query.get(hit.other) {
+            hit_event_writer.send(HitEvent {
+                entity: hit.other,
+                kind: hitable.kind,
+                position: hit.position,
+                points: maybe_hitpoints.map_or(0, |hitpoints| hitpoints.0),
+                shooter: Shooter::Cannon,
+                cause: Projectile::Laser,
+            });
+            commands.entity(hit.other).despawn();
+        }
 
-                commands.entity(laser_beam_entity).despawn();
-                commands.entity(entity).despawn();
-            }
+        if !piercing_query.contains(hit.entity) {
+            commands.entity(hit.entity).despawn();
         }
     }
 }
@@ -191,58 +246,84 @@ pub fn detect_laser_hit(
 pub fn play_enemy_hit_sound(
     mut commands: Commands,
     mut hit_event_reader: EventReader<HitEvent>,
-    sound: Res<InvaderKilledSound>,
+    game_assets: Res<GameAssets>,
 ) {
-    if !hit_event_reader.is_empty() {
-        hit_event_reader.clear();
+    // shooting down a bomb makes no sound
+    let enemy_hit = hit_event_reader
+        .read()
+        .any(|hit| matches!(hit.kind, HitKind::Invader(_) | HitKind::Ufo));
+
+    if enemy_hit {
         commands.spawn(AudioBundle {
-            source: sound.clone(),
+            source: game_assets.invader_killed_sound.clone(),
             settings: PlaybackSettings::DESPAWN,
         });
     }
 }
 
+pub fn tick_bomb_timer(time: Res<Time>, mut bomb_timer: ResMut<BombTimer>) {
+    bomb_timer.tick(time.delta());
+}
+
+pub fn bomb_timer_finished(bomb_timer: Res<BombTimer>) -> bool {
+    bomb_timer.just_finished()
+}
+
 pub fn drop_bomb(
     mut commands: Commands,
-    enemy_query: Query<(&Transform, &EnemyPosition), With<Enemy>>,
+    difficulty: Res<Difficulty>,
+    enemy_query: Query<(&Transform, &Enemy)>,
+    bomb_query: Query<(), With<Bomb>>,
+    formation: Res<Formation>,
+    atlas: Res<SpriteAtlas>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let positions = enemy_query
-        .iter()
-        .map(|(_transform, position)| position)
-        .collect::<Vec<&EnemyPosition>>();
+    let mut bombs = bomb_query.iter().count();
 
-    fn is_edge(position: &EnemyPosition, positions: &Vec<&EnemyPosition>) -> bool {
-        positions
-            .iter()
-            .filter(|grid_position| grid_position.x == position.x)
-            .map(|column_position| column_position.y)
-            .max()
-            .unwrap()
-            <= position.y
-    }
-
-    for (transform, position) in enemy_query.iter() {
-        if !is_edge(position, &positions) {
-            continue;
+    for (transform, enemy) in enemy_query.iter_many(formation.shooters()) {
+        if bombs >= difficulty.max_bombs {
+            return;
         }
 
-        if random::<f32>() * 100. <= 10. {
-            commands.spawn(BombBundle::new(
-                transform.translation.x,
-                transform.translation.y,
+        if rng.gen::<f32>() <= difficulty.bomb_chance {
+            bombs += 1;
+            commands.spawn((
+                BombBundle::new(
+                    transform.translation.x,
+                    transform.translation.y,
+                    enemy.kind,
+                    &atlas,
+                ),
+                StateScoped(GameState::Playing),
             ));
         }
     }
 }
 
+// the bombs start where the invaders dropping them are
+pub fn report_dropped_bombs(
+    bomb_query: Query<(&Transform, &Bomb), Added<Bomb>>,
+    mut shot_fired_event_writer: EventWriter<ShotFired>,
+) {
+    for (transform, bomb) in bomb_query.iter() {
+        shot_fired_event_writer.send(ShotFired {
+            shooter: Shooter::Invader(bomb.dropped_by),
+            projectile: Projectile::Bomb,
+            position: transform.translation.truncate(),
+        });
+    }
+}
+
 pub fn move_bomb(
     mut commands: Commands,
+    time: Res<Time>,
+    difficulty: Res<Difficulty>,
     mut bomb_query: Query<(Entity, &mut Transform), With<Bomb>>,
 ) {
     bomb_query
         .iter_mut()
         .for_each(|(entity, mut bomb_transform)| {
-            bomb_transform.translation.y -= 1. * BOMB_SPEED;
+            bomb_transform.translation.y -= difficulty.bomb_speed * time.delta_seconds();
 
             if bomb_transform.translation.y <= BOTTOM_WALL {
                 commands.entity(entity).despawn();
@@ -250,145 +331,449 @@ pub fn move_bomb(
         });
 }
 
-pub fn detect_bomb_hit(
+pub fn resolve_bomb_hits(
     mut commands: Commands,
-    bomb_query: Query<(Entity, &Transform), With<Bomb>>,
-    cannon_qery: Query<(&Transform, &Size), With<Cannon>>,
+    mut collision_event_reader: EventReader<Collision>,
+    bomb_query: Query<&Bomb>,
     mut player_hit_event_writer: EventWriter<PlayerHitEvent>,
-    mut player: ResMut<Player>,
 ) {
-    let (cannon_transform, size) = cannon_qery.single();
+    for collision in collision_event_reader.read() {
+        if collision.layer != CollisionLayer::EnemyShot
+            || collision.other_layer != CollisionLayer::Cannon
+        {
+            continue;
+        }
+
+        // the bomb may have been shot down by the laser in this very frame
+        let Ok(bomb) = bomb_query.get(collision.entity) else {
+            continue;
+        };
 
-    let cannon_bounding_box = Aabb2d::new(
-        cannon_transform.translation.truncate(),
-        Vec2::new(size.width, size.height) / 2.,
-    );
+        commands.entity(collision.entity).despawn();
 
-    for (bomb_entity, bomb_transform) in bomb_query.iter() {
-        let bomb_bounding_box = Aabb2d::new(
-            bomb_transform.translation.truncate(),
-            bomb_transform.scale.truncate() / 2.,
-        );
+        player_hit_event_writer.send(PlayerHitEvent {
+            position: collision.position,
+            shooter: Shooter::Invader(bomb.dropped_by),
+            cause: Projectile::Bomb,
+        });
+    }
+}
 
-        if bomb_bounding_box.intersects(&cannon_bounding_box) {
-            commands.entity(bomb_entity).despawn();
+pub fn score_hits(mut player: ResMut<Player>, mut hit_event_reader: EventReader<HitEvent>) {
+    for hit in hit_event_reader.read() {
+        player.add_to_score(hit.points);
+    }
+}
 
-            player.kill();
+pub fn count_shots(
+    mut player: ResMut<Player>,
+    mut shot_fired_event_reader: EventReader<ShotFired>,
+    mut hit_event_reader: EventReader<HitEvent>,
+) {
+    for shot in shot_fired_event_reader.read() {
+        if shot.shooter == Shooter::Cannon {
+            player.shots_fired += 1;
+            player.level_shots_fired += 1;
+        }
+    }
 
-            player_hit_event_writer.send_default();
+    for hit in hit_event_reader.read() {
+        player.hits += 1;
+        player.level_hits += 1;
+        match hit.kind {
+            HitKind::Invader(_) => player.invaders_destroyed += 1,
+            HitKind::Ufo => player.ufos_hit += 1,
+            HitKind::Bomb => {}
         }
     }
 }
 
-pub fn update_lifes_ui(player: Res<Player>, mut lifes_ui_query: Query<&mut Text, With<LifesUI>>) {
+pub fn lose_lifes(
+    mut player: ResMut<Player>,
+    mut player_hit_event_reader: EventReader<PlayerHitEvent>,
+) {
+    for _ in player_hit_event_reader.read() {
+        player.kill();
+    }
+}
+
+pub fn update_lifes_ui(
+    player: Res<Player>,
+    mut lifes_ui_query: Query<&mut BitmapText, With<LifesUI>>,
+) {
     let mut text = lifes_ui_query.single_mut();
-    text.sections[0].value = player.lifes_left();
+    let lifes_left = player.lifes_left();
+
+    // only touch the text when it changed, as every change rebuilds its glyphs
+    if text.value != lifes_left {
+        text.value = lifes_left;
+    }
 }
 
-pub fn update_score_ui(player: Res<Player>, mut score_ui_query: Query<&mut Text, With<ScoreUI>>) {
+pub fn update_score_ui(
+    player: Res<Player>,
+    mut score_ui_query: Query<&mut BitmapText, With<ScoreUI>>,
+) {
     let mut text = score_ui_query.single_mut();
-    text.sections[0].value = player.score();
+    let score = player.score();
+
+    if text.value != score {
+        text.value = score;
+    }
 }
 
-pub fn setup_ufo_timer(mut commands: Commands) {
-    commands.spawn(UfoSpawnTimer(Timer::from_seconds(
-        10.,
-        TimerMode::Repeating,
-    )));
+pub fn setup_ufo_timer(mut commands: Commands, difficulty: Res<Difficulty>) {
+    commands.spawn((
+        UfoSpawnTimer(Timer::new(difficulty.ufo_interval, TimerMode::Repeating)),
+        StateScoped(GameState::Playing),
+    ));
+}
+
+pub fn tick_ufo_spawn_timer(time: Res<Time>, mut timer_query: Query<&mut UfoSpawnTimer>) {
+    for mut timer in timer_query.iter_mut() {
+        timer.tick(time.delta());
+    }
+}
+
+pub fn ufo_spawn_timer_finished(timer_query: Query<&UfoSpawnTimer>) -> bool {
+    timer_query.iter().any(|timer| timer.just_finished())
 }
 
 pub fn move_ufo(
     mut commands: Commands,
+    time: Res<Time>,
     mut ufo_query: Query<(Entity, &mut Transform, &UfoDirection), With<Ufo>>,
+    mut ufo_escaped_event_writer: EventWriter<UfoEscaped>,
 ) {
-    if ufo_query.is_empty() {
-        return;
-    }
-
-    let (entity, mut ufo_transform, direction) = ufo_query.single_mut();
-    ufo_transform.translation.x += UFO_SPEED * direction.0;
-
-    if ufo_transform.translation.x > RIGHT_WALL + SPRITE_SIZE
-        || ufo_transform.translation.x < LEFT_WALL - SPRITE_SIZE
-    {
-        commands.entity(entity).despawn();
+    for (entity, mut ufo_transform, direction) in ufo_query.iter_mut() {
+        ufo_transform.translation.x += UFO_SPEED * direction.0 * time.delta_seconds();
+
+        if ufo_transform.translation.x > RIGHT_WALL + SPRITE_SIZE
+            || ufo_transform.translation.x < LEFT_WALL - SPRITE_SIZE
+        {
+            commands.entity(entity).despawn();
+            ufo_escaped_event_writer.send(UfoEscaped {
+                position: ufo_transform.translation.truncate(),
+            });
+        }
     }
 }
 
 pub fn check_game_over(
-    mut commands: Commands,
     player: Res<Player>,
-    cannons: Query<Entity, With<Cannon>>,
-    enemies: Query<&Transform, With<Enemy>>,
+    formation: Res<Formation>,
+    level: Res<Level>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut game_over_event_writer: EventWriter<GameOverEvent>,
 ) {
     let lifes_left = player.lifes;
-    let enemies_landed = !enemies
-        .iter()
-        .filter(|transform| transform.translation.y < BOTTOM_WALL + SPRITE_SIZE)
-        .collect::<Vec<&Transform>>()
-        .is_empty();
+    // the lowest invaders are down to where the cannon is
+    let enemies_landed = formation
+        .bounds()
+        .is_some_and(|bounds| bounds.min.y + SPRITE_SIZE / 2. < BOTTOM_WALL + SPRITE_SIZE);
 
     if lifes_left == 0 || enemies_landed {
         info!("GAME OVER");
-        commands.spawn((
-            TextBundle::from_section(
-                "GAME OVER",
-                TextStyle {
-                    font_size: TEXT_SIZE,
-                    color: TEXT_COLOR,
-                    ..default()
-                },
-            )
-            .with_style(Style {
-                position_type: PositionType::Relative,
-                top: Val::Px(100.),
-                left: Val::Px(100.),
-                ..default()
-            }),
-            GameOverSign,
-        ));
-
-        commands.insert_resource(Time::<Fixed>::from_duration(Duration::from_secs(1000)));
-        if let Ok(cannon) = cannons.get_single() {
-            commands.entity(cannon).despawn();
-        }
+        game_over_event_writer.send(GameOverEvent {
+            cause: match enemies_landed {
+                true => GameOverCause::InvadersLanded,
+                false => GameOverCause::NoLifesLeft,
+            },
+            level: level.0,
+            score: player.score,
+        });
         next_state.set(GameState::GameOver);
     }
 }
 
+// the fixed ticks start from scratch with each round, whatever time was left over from
+// the frames before, so a replay ticks on the same frames it was recorded on
+pub fn discard_fixed_overstep(mut time: ResMut<Time<Fixed>>) {
+    let overstep = time.overstep();
+    time.discard_overstep(overstep);
+}
+
 pub fn check_level_complete(
-    enemies: Query<Entity, With<Enemy>>,
+    formation: Res<Formation>,
+    player: Res<Player>,
+    level: Res<Level>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut level_cleared_event_writer: EventWriter<LevelCleared>,
 ) {
-    if enemies.is_empty() {
+    if formation.remaining() == 0 {
         info!("Level complete");
+        level_cleared_event_writer.send(LevelCleared {
+            level: level.0,
+            score: player.score,
+        });
         next_state.set(GameState::LevelComplete);
     }
 }
 
-pub fn start_next_level(mut level: ResMut<Level>, mut next_state: ResMut<NextState<GameState>>) {
-    // increase level, which translates to the row that enemies get spawned on
-    if level.0 < 10. {
-        level.0 += 1.;
-    } else {
-        // wrap back to 1, as in the original game
-        level.0 = 1.;
-    }
+pub fn start_next_level(
+    mut commands: Commands,
+    mut level: ResMut<Level>,
+    mut player: ResMut<Player>,
+) {
+    // the height the invaders start at is looked up for the round when they are spawned
+    level.0 += 1;
     info!("{:?}", level);
 
-    next_state.set(GameState::Playing);
+    // the bonuses count even when the transition is skipped
+    let transition = LevelTransition::new(player.level_bonuses(), player.score);
+    player.add_to_score(transition.total_bonus());
+    commands.insert_resource(transition);
 }
 
-pub fn handle_menu_buttons(
-    interaction_query: Query<(&Interaction, &ButtonAction), With<Button>>,
+pub fn spawn_level_transition(mut commands: Commands, transition: Res<LevelTransition>) {
+    let lines = [LevelTransitionLine::Round]
+        .into_iter()
+        .chain((0..transition.bonuses.len()).map(LevelTransitionLine::Bonus))
+        .chain([LevelTransitionLine::Score, LevelTransitionLine::Countdown]);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(TEXT_SIZE),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(GameState::LevelComplete),
+        ))
+        .with_children(|parent| {
+            // the texts are filled in by update_level_transition_ui
+            for line in lines {
+                parent.spawn((BitmapTextBundle::new("", TEXT_SIZE), line));
+            }
+        });
+}
+
+pub fn advance_level_transition(
+    time: Res<Time>,
+    mut transition: ResMut<LevelTransition>,
+    mut fired_event_reader: EventReader<Fired>,
     mut next_state: ResMut<NextState<GameState>>,
+) {
+    transition.elapsed += time.delta();
+
+    // fire skips right to the next round
+    let skipped = fired_event_reader.read().count() > 0;
+
+    if skipped || transition.is_finished() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+// a fire from before the transition must not skip it, nor the one skipping it fire a shot
+pub fn discard_fired(mut fired_events: ResMut<Events<Fired>>) {
+    fired_events.clear();
+}
+
+pub fn update_level_transition_ui(
+    transition: Res<LevelTransition>,
+    level: Res<Level>,
+    mut line_query: Query<(&LevelTransitionLine, &mut BitmapText)>,
+) {
+    for (line, mut text) in line_query.iter_mut() {
+        let value = match line {
+            LevelTransitionLine::Round => format!("ROUND {}", level.0),
+            LevelTransitionLine::Bonus(index) => {
+                let (name, points) = &transition.bonuses[*index];
+                format!("{} {}", name, points)
+            }
+            LevelTransitionLine::Score => format!("Score {}", transition.tallied_score()),
+            LevelTransitionLine::Countdown => match transition.countdown() {
+                Some(seconds) => seconds.to_string(),
+                None => String::new(),
+            },
+        };
+
+        if text.value != value {
+            text.value = value;
+        }
+    }
+}
+
+// whether any connected gamepad just pressed the button
+fn gamepad_just_pressed(
+    gamepads: &Gamepads,
+    gamepad_buttons: &ButtonInput<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+// the arrow keys and the d-pad move the focus, and so does hovering over a button
+pub fn focus_menu_buttons(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut menu_query: Query<&mut Menu>,
+    interaction_query: Query<(Entity, &Interaction), Changed<Interaction>>,
+) {
+    let Ok(mut menu) = menu_query.get_single_mut() else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::ArrowUp)
+        || gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::DPadUp)
+    {
+        menu.move_focus(-1);
+    }
+    if keys.just_pressed(KeyCode::ArrowDown)
+        || gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::DPadDown)
+    {
+        menu.move_focus(1);
+    }
+
+    for (entity, interaction) in interaction_query.iter() {
+        if *interaction == Interaction::Hovered {
+            if let Some(focus) = menu.buttons.iter().position(|button| *button == entity) {
+                menu.focus = focus;
+            }
+        }
+    }
+}
+
+// enter, space and the south button of a gamepad press the focused button
+pub fn select_menu_buttons(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    menu_query: Query<&Menu>,
+    button_query: Query<&ButtonAction>,
+    interaction_query: Query<(&Interaction, &ButtonAction), Changed<Interaction>>,
+    mut menu_selected_writer: EventWriter<MenuSelected>,
 ) {
     for (interaction, button_action) in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
-            match button_action {
-                ButtonAction::StartGame => next_state.set(GameState::Playing),
+            menu_selected_writer.send(MenuSelected(*button_action));
+        }
+    }
+
+    let pressed = keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space])
+        || gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::South);
+    if !pressed {
+        return;
+    }
+
+    let focused = menu_query.get_single().ok().and_then(Menu::focused);
+    if let Some(button_action) = focused.and_then(|button| button_query.get(button).ok()) {
+        menu_selected_writer.send(MenuSelected(*button_action));
+    }
+}
+
+pub fn highlight_focused_button(
+    themes: Res<Themes>,
+    menu_query: Query<&Menu>,
+    mut button_query: Query<(Entity, &mut BorderColor), With<ButtonAction>>,
+) {
+    let focused = menu_query.get_single().ok().and_then(Menu::focused);
+
+    for (entity, mut border_color) in button_query.iter_mut() {
+        let color = match Some(entity) == focused {
+            true => themes.current().text_color,
+            false => Color::NONE,
+        };
+
+        if border_color.0 != color {
+            border_color.0 = color;
+        }
+    }
+}
+
+pub fn handle_menu_buttons(
+    mut menu_selected_reader: EventReader<MenuSelected>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut themes: ResMut<Themes>,
+    mut overlay: ResMut<Overlay>,
+    mut after_loading: ResMut<AfterLoading>,
+    mut settings: ResMut<GameSettings>,
+) {
+    for MenuSelected(button_action) in menu_selected_reader.read() {
+        match button_action {
+            ButtonAction::StartGame => next_state.set(GameState::Playing),
+            ButtonAction::Options => next_state.set(GameState::Options),
+            ButtonAction::CycleTheme => {
+                // reload the assets of the new theme and come back here
+                themes.select_next();
+                after_loading.0 = GameState::Options;
+                next_state.set(GameState::Loading);
             }
+            ButtonAction::ToggleOverlay => overlay.enabled = !overlay.enabled,
+            ButtonAction::CycleDifficulty => {
+                // each difficulty comes with its own number of lifes
+                settings.difficulty = settings.difficulty.next();
+                settings.lifes = settings.difficulty.lifes();
+            }
+            ButtonAction::ToggleAdaptive => settings.adaptive = !settings.adaptive,
+            ButtonAction::Resume => next_pause_state.set(PauseState::Running),
+            ButtonAction::Back => next_state.set(GameState::Menu),
+        }
+    }
+}
+
+// escape, p and the start button of a gamepad pause the game and carry on with it
+pub fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if keys.any_just_pressed([KeyCode::Escape, KeyCode::KeyP])
+        || gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::Start)
+    {
+        next_pause_state.set(match pause_state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+}
+
+// stops the clock everything in the game runs on, the fixed ticks included
+pub fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    info!("Paused");
+    time.pause();
+}
+
+pub fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    info!("Resumed");
+    time.unpause();
+}
+
+pub fn update_option_labels(
+    themes: Res<Themes>,
+    overlay: Res<Overlay>,
+    settings: Res<GameSettings>,
+    mut label_query: Query<(&OptionLabel, &mut BitmapText)>,
+) {
+    for (label, mut text) in label_query.iter_mut() {
+        let value = match label {
+            OptionLabel::Theme => format!("Theme: {}", themes.current().name),
+            OptionLabel::Overlay => match overlay.enabled {
+                true => "Overlay: On".into(),
+                false => "Overlay: Off".into(),
+            },
+            OptionLabel::Difficulty => format!("Difficulty: {}", settings.difficulty),
+            OptionLabel::Adaptive => match settings.adaptive {
+                true => "Adaptive: On".into(),
+                false => "Adaptive: Off".into(),
+            },
+        };
+
+        if text.value != value {
+            text.value = value;
         }
     }
 }
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+use crate::prelude::*;
+
+const THEMES_DIRECTORY: &str = "themes";
+const MANIFEST_FILE_NAME: &str = "theme.ron";
+
+#[derive(Deserialize, Debug)]
+struct ThemeManifest {
+    name: String,
+    sprite_color: Option<(f32, f32, f32)>,
+    text_color: Option<(f32, f32, f32)>,
+    background_color: Option<(f32, f32, f32)>,
+    overlay: Option<Vec<OverlayBandManifest>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OverlayBandManifest {
+    from: f32,
+    to: f32,
+    color: (f32, f32, f32),
+}
+
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    directory: Option<String>,
+    // asset paths, relative to the theme directory, that the theme replaces
+    overrides: HashSet<String>,
+    pub sprite_color: Color,
+    pub text_color: Color,
+    pub background_color: Color,
+    pub overlay_bands: Vec<OverlayBand>,
+}
+
+impl Theme {
+    // any asset the theme doesn't ship is taken from the default theme
+    pub fn asset_path(&self, path: &str) -> String {
+        match &self.directory {
+            Some(directory) if self.overrides.contains(path) => {
+                format!("{}/{}/{}", THEMES_DIRECTORY, directory, path)
+            }
+            _ => path.into(),
+        }
+    }
+
+    fn load(files: &[String], directory: &str) -> Result<Self, String> {
+        let theme_path = format!("{}/{}/", THEMES_DIRECTORY, directory);
+        let manifest = read_asset(&format!("{}{}", theme_path, MANIFEST_FILE_NAME))
+            .ok_or(format!("missing {}", MANIFEST_FILE_NAME))?;
+        // lets the manifest leave out the Some(..) around the optional fields
+        let manifest: ThemeManifest = ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_bytes(&manifest)
+            .map_err(|error| error.to_string())?;
+
+        let overrides = files
+            .iter()
+            .filter_map(|path| path.strip_prefix(&theme_path))
+            .map(String::from)
+            .collect();
+
+        let default = Theme::default();
+        let color = |color: Option<(f32, f32, f32)>, fallback: Color| {
+            color.map_or(fallback, |(r, g, b)| Color::srgb(r, g, b))
+        };
+
+        Ok(Self {
+            name: manifest.name,
+            directory: Some(directory.into()),
+            overrides,
+            sprite_color: color(manifest.sprite_color, default.sprite_color),
+            text_color: color(manifest.text_color, default.text_color),
+            background_color: color(manifest.background_color, default.background_color),
+            overlay_bands: manifest.overlay.map_or(default.overlay_bands, |bands| {
+                bands
+                    .into_iter()
+                    .map(|band| OverlayBand {
+                        from: band.from,
+                        to: band.to,
+                        color: color(Some(band.color), SPRITE_COLOR),
+                    })
+                    .collect()
+            }),
+        })
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "Classic".into(),
+            directory: None,
+            overrides: HashSet::new(),
+            sprite_color: SPRITE_COLOR,
+            text_color: TEXT_COLOR,
+            background_color: Color::BLACK,
+            overlay_bands: Overlay::default().bands,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct Themes {
+    themes: Vec<Theme>,
+    selected: usize,
+}
+
+impl Themes {
+    // the default theme followed by every directory in assets/themes with a manifest
+    pub fn discover() -> Self {
+        let files = asset_files();
+        let mut directories = files
+            .iter()
+            .filter_map(|path| path.strip_prefix(&format!("{}/", THEMES_DIRECTORY)))
+            .filter_map(|path| path.split_once('/'))
+            .map(|(directory, _)| directory.to_string())
+            .collect::<Vec<String>>();
+        directories.sort();
+        directories.dedup();
+
+        let mut themes = vec![Theme::default()];
+        for directory in directories {
+            match Theme::load(&files, &directory) {
+                Ok(theme) => themes.push(theme),
+                Err(error) => warn!("Skipping theme {}: {}", directory, error),
+            }
+        }
+
+        Self {
+            themes,
+            selected: 0,
+        }
+    }
+
+    pub fn current(&self) -> &Theme {
+        &self.themes[self.selected]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.themes.len();
+        info!("Selected theme {}", self.current().name);
+    }
+}
+
+pub fn apply_theme(
+    mut commands: Commands,
+    themes: Res<Themes>,
+    mut overlay: ResMut<Overlay>,
+    mut text_query: Query<&mut BitmapText>,
+) {
+    let theme = themes.current();
+
+    commands.insert_resource(ClearColor(theme.background_color));
+    overlay.bands = theme.overlay_bands.clone();
+
+    // rebuild the glyphs with the font and colors of the theme
+    text_query
+        .iter_mut()
+        .for_each(|mut text| text.set_changed());
+}